@@ -0,0 +1,222 @@
+//! Unread badge on the app icon
+//!
+//! macOS shows the count on the dock tile, Windows as one of the overlay icons
+//! in `icons/badge` on the taskbar button, and Linux through the Unity `LauncherEntry` DBus API,
+//! which most docks and panels (Dash to Dock, Plasma, Plank) understand.
+
+use tauri::AppHandle;
+
+#[cfg(target_os = "macos")]
+use cocoa::base::{id, nil};
+#[cfg(target_os = "macos")]
+use cocoa::foundation::NSString;
+#[cfg(target_os = "macos")]
+use objc::{class, msg_send, sel, sel_impl};
+
+#[cfg(target_os = "windows")]
+use tauri::Manager;
+
+#[cfg(target_os = "linux")]
+use crate::linux_bus::session_bus;
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+
+/// Largest count shown on the macOS dock tile before it reads "999+"
+#[cfg(target_os = "macos")]
+const MAX_DOCK_COUNT: u32 = 999;
+
+/// Largest count that fits the Windows overlay icon before it reads "9+"
+#[cfg(any(target_os = "windows", test))]
+const MAX_OVERLAY_COUNT: u32 = 9;
+
+/// DBus interface of the Unity launcher API
+#[cfg(target_os = "linux")]
+const LAUNCHER_ENTRY_INTERFACE: &str = "com.canonical.Unity.LauncherEntry";
+
+/// Object path the launcher entry signals are sent from
+#[cfg(target_os = "linux")]
+const LAUNCHER_ENTRY_PATH: &str = "/com/cushion/desktop/LauncherEntry";
+
+/// Show `count` on the app icon, or remove the badge if it's 0
+#[cfg(target_os = "macos")]
+pub fn set_badge_count(app: &AppHandle, count: u32) -> Result<(), String> {
+    let label = badge_text(count, MAX_DOCK_COUNT);
+
+    // AppKit objects must only be touched from the main thread
+    app.run_on_main_thread(move || unsafe {
+        let ns_app: id = msg_send![class!(NSApplication), sharedApplication];
+        let dock_tile: id = msg_send![ns_app, dockTile];
+        let label: id = match label {
+            Some(ref label) => NSString::alloc(nil).init_str(label),
+            None => nil,
+        };
+        let _: () = msg_send![dock_tile, setBadgeLabel: label];
+    })
+    .map_err(|e| format!("Failed to set dock badge: {}", e))?;
+
+    println!("🔴 Dock badge set to {}", count);
+    Ok(())
+}
+
+/// Show `count` on the app icon, or remove the badge if it's 0
+#[cfg(target_os = "windows")]
+pub fn set_badge_count(app: &AppHandle, count: u32) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Main window not found".to_string())?;
+
+    let icon = badge_text(count, MAX_OVERLAY_COUNT).map(|text| overlay_icon(&text));
+    window
+        .set_overlay_icon(icon)
+        .map_err(|e| format!("Failed to set taskbar badge: {}", e))?;
+
+    println!("🔴 Taskbar badge set to {}", count);
+    Ok(())
+}
+
+/// Show `count` on the app icon, or remove the badge if it's 0
+#[cfg(target_os = "linux")]
+pub fn set_badge_count(_app: &AppHandle, count: u32) -> Result<(), String> {
+    let conn = session_bus().map_err(|e| format!("Failed to connect to DBus session bus: {}", e))?;
+    let app_uri = app_uri().ok_or_else(|| "Failed to determine the app's desktop file".to_string())?;
+
+    emit_launcher_entry(&conn, &app_uri, count)
+        .map_err(|e| format!("Failed to set launcher badge: {}", e))?;
+
+    println!("🔴 Launcher badge set to {}", count);
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn set_badge_count(_app: &AppHandle, _count: u32) -> Result<(), String> {
+    Err("Badges not supported on this platform".to_string())
+}
+
+/// Text shown on the badge, or `None` for no badge
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn badge_text(count: u32, max: u32) -> Option<String> {
+    match count {
+        0 => None,
+        count if count > max => Some(format!("{}+", max)),
+        count => Some(count.to_string()),
+    }
+}
+
+/// The pre-rendered overlay icon for a badge: white text on a red circle,
+/// 32x32 so Windows can scale it down to 16x16 at 100% display scaling
+#[cfg(target_os = "windows")]
+fn overlay_icon(text: &str) -> tauri::image::Image<'static> {
+    match text {
+        "1" => tauri::include_image!("icons/badge/1.png"),
+        "2" => tauri::include_image!("icons/badge/2.png"),
+        "3" => tauri::include_image!("icons/badge/3.png"),
+        "4" => tauri::include_image!("icons/badge/4.png"),
+        "5" => tauri::include_image!("icons/badge/5.png"),
+        "6" => tauri::include_image!("icons/badge/6.png"),
+        "7" => tauri::include_image!("icons/badge/7.png"),
+        "8" => tauri::include_image!("icons/badge/8.png"),
+        "9" => tauri::include_image!("icons/badge/9.png"),
+        _ => tauri::include_image!("icons/badge/9-plus.png"),
+    }
+}
+
+/// URI identifying the app to the launcher
+///
+/// The bundler names the desktop file after the main binary, so
+/// `/usr/bin/cushion` is `application://cushion.desktop`.
+#[cfg(target_os = "linux")]
+fn app_uri() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    let name = exe.file_stem()?.to_str()?;
+    Some(format!("application://{}.desktop", name))
+}
+
+/// Send the launcher an `Update` signal with the badge count
+#[cfg(target_os = "linux")]
+fn emit_launcher_entry(conn: &zbus::blocking::Connection, app_uri: &str, count: u32) -> zbus::Result<()> {
+    let properties: HashMap<&str, zbus::zvariant::Value> = HashMap::from([
+        ("count", zbus::zvariant::Value::from(i64::from(count))),
+        ("count-visible", zbus::zvariant::Value::from(count > 0)),
+    ]);
+
+    conn.emit_signal(
+        None::<&str>,
+        LAUNCHER_ENTRY_PATH,
+        LAUNCHER_ENTRY_INTERFACE,
+        "Update",
+        &(app_uri, properties),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_text_caps_large_counts() {
+        assert_eq!(badge_text(0, MAX_OVERLAY_COUNT), None);
+        assert_eq!(badge_text(7, MAX_OVERLAY_COUNT), Some("7".to_string()));
+        assert_eq!(badge_text(9, MAX_OVERLAY_COUNT), Some("9".to_string()));
+        assert_eq!(badge_text(10, MAX_OVERLAY_COUNT), Some("9+".to_string()));
+        assert_eq!(badge_text(1_234, 999), Some("999+".to_string()));
+    }
+
+    #[test]
+    fn test_every_overlay_text_has_an_icon() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("icons/badge");
+        for count in 1..=MAX_OVERLAY_COUNT + 1 {
+            let text = badge_text(count, MAX_OVERLAY_COUNT).unwrap();
+            let file = text.replace('+', "-plus");
+            assert!(dir.join(format!("{}.png", file)).is_file(), "no icon for {}", text);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_launcher_entry_update_signal() {
+        use std::os::unix::net::UnixStream;
+        use zbus::zvariant::OwnedValue;
+
+        // Connect a fake launcher and the app over a private peer-to-peer bus
+        let (launcher_stream, app_stream) = UnixStream::pair().unwrap();
+        let guid = zbus::Guid::generate();
+        let launcher = std::thread::spawn(move || {
+            zbus::blocking::connection::Builder::unix_stream(launcher_stream)
+                .server(guid)
+                .unwrap()
+                .p2p()
+                .build()
+                .unwrap()
+        });
+        let app = zbus::blocking::connection::Builder::unix_stream(app_stream)
+            .p2p()
+            .build()
+            .unwrap();
+        let launcher = launcher.join().unwrap();
+
+        let rule = zbus::MatchRule::builder()
+            .msg_type(zbus::message::Type::Signal)
+            .interface(LAUNCHER_ENTRY_INTERFACE)
+            .unwrap()
+            .build();
+        let mut signals =
+            zbus::blocking::MessageIterator::for_match_rule(rule, &launcher, None).unwrap();
+
+        emit_launcher_entry(&app, "application://cushion.desktop", 3).unwrap();
+        emit_launcher_entry(&app, "application://cushion.desktop", 0).unwrap();
+
+        let mut next_update = || {
+            let msg = signals.next().unwrap().unwrap();
+            assert_eq!(msg.header().member().unwrap().as_str(), "Update");
+            let (app_uri, properties): (String, HashMap<String, OwnedValue>) =
+                msg.body().deserialize().unwrap();
+            assert_eq!(app_uri, "application://cushion.desktop");
+            (
+                i64::try_from(&properties["count"]).unwrap(),
+                bool::try_from(&properties["count-visible"]).unwrap(),
+            )
+        };
+        assert_eq!(next_update(), (3, true));
+        assert_eq!(next_update(), (0, false));
+    }
+}
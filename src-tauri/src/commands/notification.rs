@@ -1,46 +1,323 @@
 /// Notification commands using custom cross-platform notification system
-use crate::notifications::NotificationManager;
+use crate::notifications::{
+    ActiveNotification, BatchingSettings, HistoryEntry, HistoryRetention, ImageInput,
+    NotificationAction, NotificationCapabilities, NotificationCategory, NotificationManager,
+    NotificationPermission, NotificationPriority, NotificationReply, NotificationRequest,
+    NotificationSound, PermissionStatus, QuietHoursPolicy, ScheduledNotification, SoundProfiles,
+};
+use serde::Deserialize;
+use std::time::Duration;
 
 #[tauri::command]
 pub async fn show_notification(
     title: String,
     body: String,
-    url: Option<String>
-) -> Result<(), String> {
+    url: Option<String>,
+    actions: Option<Vec<NotificationAction>>,
+    reply: Option<NotificationReply>,
+    payload: Option<serde_json::Value>,
+    thread_id: Option<String>,
+    image: Option<ImageInput>,
+    sound: Option<NotificationSound>,
+    category: Option<NotificationCategory>,
+    priority: Option<NotificationPriority>,
+    snooze_minutes: Option<u32>,
+) -> Result<String, String> {
     println!("📱 Show notification command: '{}' - '{}'", title, body);
 
     let manager = NotificationManager::get()
         .ok_or_else(|| "Notification manager not initialized".to_string())?;
 
-    // Generate a unique notification ID
-    let id = format!("cushion-{}", uuid::Uuid::new_v4());
+    let request = build_request(
+        &manager,
+        NotificationContent {
+            title,
+            body,
+            url,
+            actions,
+            reply,
+            payload,
+            thread_id,
+            image,
+            sound,
+            category,
+            priority,
+            snooze_minutes,
+        },
+    )?;
+    manager.show_notification(request)
+}
+
+/// Content of a notification scheduled for later, with the same fields as
+/// `show_notification`'s arguments
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationContent {
+    title: String,
+    body: String,
+    url: Option<String>,
+    actions: Option<Vec<NotificationAction>>,
+    reply: Option<NotificationReply>,
+    payload: Option<serde_json::Value>,
+    thread_id: Option<String>,
+    image: Option<ImageInput>,
+    sound: Option<NotificationSound>,
+    category: Option<NotificationCategory>,
+    priority: Option<NotificationPriority>,
+    snooze_minutes: Option<u32>,
+}
 
-    manager.show_notification(id, title, body, url)
+/// Build a notification request with a new unique ID
+fn build_request(
+    manager: &NotificationManager,
+    content: NotificationContent,
+) -> Result<NotificationRequest, String> {
+    // Images are validated and cached up front so a bad one fails the command
+    let image = content
+        .image
+        .map(|image| manager.cache_image(&image))
+        .transpose()?;
+
+    Ok(NotificationRequest {
+        id: format!("cushion-{}", uuid::Uuid::new_v4()),
+        title: content.title,
+        body: content.body,
+        url: content.url,
+        actions: content.actions.unwrap_or_default(),
+        reply: content.reply,
+        payload: content.payload,
+        thread_id: content.thread_id,
+        image,
+        sound: content.sound,
+        category: content.category,
+        priority: content.priority.unwrap_or_default(),
+        snooze_minutes: content.snooze_minutes,
+        ..Default::default()
+    })
 }
 
+/// Schedule a notification to be shown at `at`, in milliseconds since the
+/// Unix epoch
 #[tauri::command]
-pub async fn check_notification_permission() -> Result<String, String> {
+pub fn schedule_notification(at: u64, notification: NotificationContent) -> Result<String, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    let request = build_request(&manager, notification)?;
+    manager.schedule_notification(at, request)
+}
+
+#[tauri::command]
+pub fn cancel_scheduled_notification(id: String) -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.cancel_scheduled_notification(&id)
+}
+
+#[tauri::command]
+pub fn list_scheduled_notifications() -> Result<Vec<ScheduledNotification>, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    Ok(manager.list_scheduled_notifications())
+}
+
+#[tauri::command]
+pub fn clear_notifications_for_thread(thread_id: String) -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.clear_notifications_for_thread(&thread_id)
+}
+
+#[tauri::command]
+pub fn dismiss_notification(id: String) -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.dismiss_notification(&id)
+}
+
+#[tauri::command]
+pub fn dismiss_all_notifications() -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.dismiss_all_notifications()
+}
+
+/// List the ids of notifications still shown by the OS
+///
+/// Asking macOS can take up to a few seconds, so it's done off the main
+/// thread and the async runtime's workers.
+#[tauri::command]
+pub async fn get_delivered_notifications() -> Result<Vec<String>, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || manager.get_delivered_notifications())
+        .await
+        .map_err(|e| format!("Failed to list delivered notifications: {}", e))?
+}
+
+#[tauri::command]
+pub fn list_active_notifications() -> Result<Vec<ActiveNotification>, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    Ok(manager.list_active_notifications())
+}
+
+#[tauri::command]
+pub fn get_quiet_hours() -> Result<QuietHoursPolicy, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    Ok(manager.quiet_hours_policy())
+}
+
+#[tauri::command]
+pub fn set_quiet_hours(policy: QuietHoursPolicy) -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.set_quiet_hours_policy(policy)
+}
+
+/// Pause notifications for the given number of minutes
+#[tauri::command]
+pub fn pause_notifications(minutes: u32) -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.pause_notifications(Duration::from_secs(u64::from(minutes) * 60))
+}
+
+#[tauri::command]
+pub fn resume_notifications() -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.resume_notifications()
+}
+
+#[tauri::command]
+pub fn get_notification_batching() -> Result<BatchingSettings, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    Ok(manager.batching_settings())
+}
+
+#[tauri::command]
+pub fn set_notification_batching(settings: BatchingSettings) -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.set_batching_settings(settings)
+}
+
+/// Search the notification history, newest first
+#[tauri::command]
+pub fn get_notification_history(
+    query: Option<String>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    Ok(manager.notification_history(
+        query.as_deref().unwrap_or_default(),
+        limit.unwrap_or(50),
+        offset.unwrap_or(0),
+    ))
+}
+
+#[tauri::command]
+pub fn clear_notification_history() -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.clear_notification_history()
+}
+
+#[tauri::command]
+pub fn get_notification_history_retention() -> Result<HistoryRetention, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    Ok(manager.history_retention())
+}
+
+#[tauri::command]
+pub fn set_notification_history_retention(retention: HistoryRetention) -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.set_history_retention(retention)
+}
+
+#[tauri::command]
+pub fn get_notification_sounds() -> Result<SoundProfiles, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    Ok(manager.sound_profiles())
+}
+
+#[tauri::command]
+pub fn set_notification_sounds(profiles: SoundProfiles) -> Result<(), String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.set_sound_profiles(profiles)
+}
+
+/// Get what the platform's notification system supports
+#[tauri::command]
+pub async fn get_notification_capabilities() -> Result<NotificationCapabilities, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    Ok(manager.capabilities())
+}
+
+/// Get the notification permission status without prompting the user
+#[tauri::command]
+pub async fn get_notification_permission_status() -> Result<NotificationPermission, String> {
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.get_permission_status()
+}
+
+/// Ask the user for permission to show notifications (macOS shows a prompt
+/// the first time)
+#[tauri::command]
+pub async fn request_notification_permission() -> Result<NotificationPermission, String> {
     println!("🔍 Requesting notification permission...");
 
-    #[cfg(target_os = "macos")]
-    {
-        crate::notifications::macos::request_notification_permission()
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        // Windows typically doesn't require permission prompts for desktop apps
-        Ok("granted".to_string())
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        // Linux typically doesn't require permission prompts
-        Ok("granted".to_string())
-    }
-
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-    {
-        Ok("denied".to_string())
-    }
+    let manager = NotificationManager::get()
+        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+
+    manager.request_permission()
+}
+
+/// Ask for permission to show notifications, answering "granted" or "denied"
+///
+/// Deprecated: kept for older web app builds. Use
+/// `request_notification_permission`, or `get_notification_permission_status`
+/// to check without prompting.
+#[tauri::command]
+pub async fn check_notification_permission() -> Result<String, String> {
+    println!("⚠️  check_notification_permission is deprecated, use request_notification_permission");
+
+    let permission = request_notification_permission().await?;
+    let status = match permission.status {
+        PermissionStatus::Granted | PermissionStatus::Provisional => "granted",
+        PermissionStatus::Denied | PermissionStatus::NotDetermined => "denied",
+    };
+    Ok(status.to_string())
 }
@@ -21,20 +21,36 @@ pub fn get_user_agent(app: tauri::AppHandle) -> String {
 }
 
 #[tauri::command]
-pub async fn open_url(app: tauri::AppHandle, url: String) -> Result<(), String> {
+pub async fn open_url(
+    app: tauri::AppHandle,
+    policy: tauri::State<'_, crate::url_policy::UrlPolicy>,
+    url: String,
+) -> Result<(), String> {
     use tauri_plugin_opener::OpenerExt;
 
-    // Parse and validate URL
-    let parsed = url::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
-
     // Only allow safe protocols
-    match parsed.scheme() {
-        "http" | "https" | "mailto" => {}
-        scheme => return Err(format!("Protocol '{}' not allowed", scheme)),
-    }
+    policy.check_external_url(&url)?;
 
     app.opener()
         .open_url(&url, None::<&str>)
         .map_err(|e| e.to_string())?;
     Ok(())
 }
+
+/// Called by the web app once its event listeners are registered, so the
+/// deep links and notification clicks held during startup are replayed
+#[tauri::command]
+pub fn frontend_ready(app: tauri::AppHandle) {
+    crate::frontend::ready(&app);
+}
+
+/// Show the unread count on the dock/taskbar icon
+#[tauri::command]
+pub fn set_badge_count(app: tauri::AppHandle, count: u32) -> Result<(), String> {
+    crate::badge::set_badge_count(&app, count)
+}
+
+#[tauri::command]
+pub fn clear_badge(app: tauri::AppHandle) -> Result<(), String> {
+    crate::badge::set_badge_count(&app, 0)
+}
@@ -1,11 +1,12 @@
 use tauri::{AppHandle, Manager};
-use tauri_plugin_updater::UpdaterExt;
+
+use crate::updater::{build_updater, PendingUpdate, UpdateChannel, UpdateState};
 
 #[tauri::command]
 pub async fn check_for_updates(app: AppHandle) -> Result<Option<String>, String> {
     println!("🔄 Checking for updates...");
 
-    match app.updater_builder().build() {
+    match build_updater(&app) {
         Ok(updater) => {
             match updater.check().await {
                 Ok(Some(update)) => {
@@ -15,6 +16,7 @@ pub async fn check_for_updates(app: AppHandle) -> Result<Option<String>, String>
                 }
                 Ok(None) => {
                     println!("✅ App is up to date");
+                    crate::updater::finish_downgrade(&app);
                     Ok(None)
                 }
                 Err(e) => {
@@ -34,31 +36,19 @@ pub async fn check_for_updates(app: AppHandle) -> Result<Option<String>, String>
 pub async fn install_update(app: AppHandle) -> Result<(), String> {
     println!("📥 Installing update...");
 
-    match app.updater_builder().build() {
+    match build_updater(&app) {
         Ok(updater) => {
             match updater.check().await {
                 Ok(Some(update)) => {
-                    println!("⬇️  Downloading update: {}", update.version);
+                    // Progress is sent to the webview as `update-progress`
+                    // events, and `cancel_update_download` stops it
+                    crate::updater::download_update(&app, update).await?;
 
-                    // Download and install the update
-                    match update.download_and_install(|chunk_length, content_length| {
-                        if let Some(total) = content_length {
-                            let percentage = (chunk_length as f64 / total as f64) * 100.0;
-                            println!("📊 Download progress: {:.1}%", percentage);
-                        }
-                    }, || {
-                        println!("✅ Update downloaded, installing...");
-                    }).await {
-                        Ok(_) => {
-                            println!("🎉 Update installed successfully!");
-                            println!("🔄 Restarting application...");
-                            Ok(())
-                        }
-                        Err(e) => {
-                            println!("❌ Failed to install update: {}", e);
-                            Err(format!("Failed to install update: {}", e))
-                        }
-                    }
+                    println!("✅ Update downloaded, installing...");
+                    crate::updater::install_staged_update(&app)?;
+                    println!("🎉 Update installed successfully!");
+                    println!("🔄 Restarting application...");
+                    Ok(())
                 }
                 Ok(None) => {
                     println!("ℹ️  No update available");
@@ -77,6 +67,50 @@ pub async fn install_update(app: AppHandle) -> Result<(), String> {
     }
 }
 
+/// Get the progress of the update downloaded in the background, if any
+#[tauri::command]
+pub fn get_update_state(pending: tauri::State<'_, PendingUpdate>) -> Option<UpdateState> {
+    pending.state()
+}
+
+/// Stop the update download in progress
+///
+/// Returns whether there was a download to cancel.
+#[tauri::command]
+pub fn cancel_update_download(pending: tauri::State<'_, PendingUpdate>) -> bool {
+    pending.cancel_download()
+}
+
+/// Install the update downloaded in the background and restart
+#[tauri::command]
+pub fn restart_to_update(app: AppHandle) -> Result<(), String> {
+    crate::updater::restart_to_update(&app)
+}
+
+/// Get the channel updates are checked on: "stable", "beta" or "dev"
+#[tauri::command]
+pub fn get_update_channel(app: AppHandle) -> UpdateChannel {
+    crate::updater::current_channel(&app)
+}
+
+/// List the channels this build can switch to
+///
+/// Production builds offer "stable" and "beta"; developer builds only "dev".
+#[tauri::command]
+pub fn get_available_update_channels(app: AppHandle) -> Vec<UpdateChannel> {
+    crate::updater::available_channels(&app)
+}
+
+/// Switch update channel
+///
+/// Switching to a more stable channel asks the user to confirm first;
+/// returns whether the channel was switched. Fails for a channel of another
+/// build flavour.
+#[tauri::command]
+pub async fn set_update_channel(app: AppHandle, channel: UpdateChannel) -> Result<bool, String> {
+    crate::updater::switch_channel(&app, channel).await
+}
+
 #[tauri::command]
 pub fn get_app_version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
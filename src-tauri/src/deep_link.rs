@@ -0,0 +1,496 @@
+//! Deep link routing
+//!
+//! `cushion://` URLs are parsed into typed routes. Routes into the web app
+//! are emitted to the webview as a `deep-link-route` event (the raw URL is
+//! still emitted as `deep-link` for older listeners); `cushion://update` and
+//! `cushion://settings/notifications` are handled natively. Links to routes
+//! the router doesn't know are passed on as the raw `deep-link` event alone,
+//! as before routing existed; malformed ones are rejected and the reason
+//! logged.
+
+use crate::url_policy::UrlPolicy;
+use serde::Serialize;
+use tauri::{AppHandle, Listener, Manager};
+use url::Url;
+
+/// Longest id or slug accepted in a route
+const MAX_ID_LEN: usize = 128;
+
+/// System settings page for notifications
+#[cfg(target_os = "macos")]
+const NOTIFICATION_SETTINGS_URL: &str =
+    "x-apple.systempreferences:com.apple.preference.notifications";
+
+/// System settings page for notifications
+#[cfg(target_os = "windows")]
+const NOTIFICATION_SETTINGS_URL: &str = "ms-settings:notifications";
+
+/// A page of the web app a deep link points to
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Route {
+    Post {
+        id: String,
+    },
+    Thread {
+        id: String,
+    },
+    User {
+        id: String,
+    },
+    Workspace {
+        slug: String,
+    },
+    Settings {
+        section: Option<String>,
+    },
+    /// Sign-in redirect from the browser
+    AuthCallback {
+        code: String,
+        state: Option<String>,
+    },
+    /// Magic link sign-in from an email
+    AuthSuccess {
+        token: String,
+        /// Page to show once signed in, always a path within the web app
+        #[serde(rename = "callbackUrl")]
+        callback_url: Option<String>,
+    },
+}
+
+/// A deep link handled by the app rather than the webview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeRoute {
+    /// Check for updates, prompting to install one
+    Update,
+    /// Open the system notification settings for the app
+    NotificationSettings,
+}
+
+/// Where a deep link goes
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLink {
+    Web(Route),
+    Native(NativeRoute),
+    /// A route the router doesn't know, left to the webview's `deep-link`
+    /// listener
+    Unrouted,
+}
+
+/// Payload of the `deep-link-route` event emitted to the webview
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLinkEvent {
+    /// The deep link as opened
+    pub url: String,
+    pub route: Route,
+}
+
+/// Parse a deep link into its route, or the reason it's rejected
+pub fn parse(policy: &UrlPolicy, url: &str) -> Result<DeepLink, String> {
+    let parsed = Url::parse(url).map_err(|e| format!("invalid URL: {}", e))?;
+    if !policy.is_deep_link(url) {
+        return Err(format!("'{}' is not a deep link scheme", parsed.scheme()));
+    }
+
+    // `cushion://post/1` has the route name as its host
+    let path = parsed.path().split('/');
+    let segments: Vec<&str> = parsed
+        .host_str()
+        .into_iter()
+        .chain(path)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    let (name, rest) = segments
+        .split_first()
+        .ok_or_else(|| "empty route".to_string())?;
+
+    let route = match (name.to_ascii_lowercase().as_str(), rest) {
+        ("post", [id]) => Route::Post {
+            id: validate_id(id)?,
+        },
+        ("thread", [id]) => Route::Thread {
+            id: validate_id(id)?,
+        },
+        ("user", [id]) => Route::User {
+            id: validate_id(id)?,
+        },
+        ("workspace", [slug]) => Route::Workspace {
+            slug: validate_id(slug)?,
+        },
+        ("post" | "thread" | "user" | "workspace", _) => {
+            return Err(format!("'{}' takes exactly one id", name));
+        }
+        ("settings", []) => Route::Settings { section: None },
+        ("settings", [section]) if section.eq_ignore_ascii_case("notifications") => {
+            return Ok(DeepLink::Native(NativeRoute::NotificationSettings));
+        }
+        ("settings", [section]) => Route::Settings {
+            section: Some(validate_id(section)?),
+        },
+        ("auth", ["callback"]) => {
+            let query = |key: &str| query(&parsed, key);
+            Route::AuthCallback {
+                code: query("code")
+                    .filter(|code| !code.is_empty())
+                    .ok_or_else(|| "auth callback without a code".to_string())?,
+                state: query("state"),
+            }
+        }
+        ("auth", ["success"]) => Route::AuthSuccess {
+            token: query(&parsed, "token")
+                .filter(|token| !token.is_empty())
+                .ok_or_else(|| "auth success without a token".to_string())?,
+            callback_url: query(&parsed, "callbackUrl")
+                .map(|path| validate_callback_path(&path))
+                .transpose()?,
+        },
+        ("update", []) => return Ok(DeepLink::Native(NativeRoute::Update)),
+        ("settings" | "auth" | "update", _) => {
+            return Err(format!("unknown route '{}'", segments.join("/")));
+        }
+        _ => return Ok(DeepLink::Unrouted),
+    };
+    Ok(DeepLink::Web(route))
+}
+
+/// The first value of `key` in the query string
+fn query(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+}
+
+/// Check a page to open after signing in, which must be a path within the
+/// web app rather than a URL that could lead elsewhere
+fn validate_callback_path(path: &str) -> Result<String, String> {
+    let valid = path.starts_with('/')
+        && !path.starts_with("//")
+        && !path.contains('\\')
+        && !path.chars().any(char::is_control);
+    if valid {
+        Ok(path.to_string())
+    } else {
+        Err(format!("callback '{}' is not a path", path))
+    }
+}
+
+/// Check an id or slug taken from a route
+fn validate_id(id: &str) -> Result<String, String> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(id.to_string())
+    } else {
+        Err(format!("invalid id '{}'", id))
+    }
+}
+
+/// Route a deep link opened from outside the app
+pub fn open(app: &AppHandle, url: &str) {
+    let policy = app.state::<UrlPolicy>();
+    match parse(&policy, url) {
+        Ok(DeepLink::Web(route)) => {
+            println!("🔗 Routing deep link: {:?}", route);
+            emit_route(app, url, route);
+        }
+        Ok(DeepLink::Native(route)) => {
+            println!("🔗 Handling deep link natively: {:?}", route);
+            handle_native(app, url, route);
+        }
+        Ok(DeepLink::Unrouted) => {
+            println!("🔗 Passing unrouted deep link to the webview: {}", url);
+            crate::frontend::emit(app, "deep-link", url);
+            show_main_window(app);
+        }
+        Err(reason) => {
+            eprintln!("🚫 Rejected deep link {}: {}", url, reason);
+        }
+    }
+}
+
+fn handle_native(app: &AppHandle, url: &str, route: NativeRoute) {
+    match route {
+        NativeRoute::Update => crate::updater::show_update_dialog(app, true),
+        NativeRoute::NotificationSettings => open_notification_settings(app, url),
+    }
+}
+
+/// Open the system notification settings
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn open_notification_settings(app: &AppHandle, _url: &str) {
+    use tauri_plugin_opener::OpenerExt;
+
+    if let Err(e) = app
+        .opener()
+        .open_url(NOTIFICATION_SETTINGS_URL, None::<&str>)
+    {
+        eprintln!("❌ Failed to open notification settings: {}", e);
+    }
+}
+
+/// Open the notification settings
+///
+/// Linux desktops have no common settings URL, so the web app's own
+/// notification settings are shown instead.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn open_notification_settings(app: &AppHandle, url: &str) {
+    let route = Route::Settings {
+        section: Some("notifications".to_string()),
+    };
+    emit_route(app, url, route);
+}
+
+/// Emit a route to the webview and bring the window forward
+fn emit_route(app: &AppHandle, url: &str, route: Route) {
+    crate::frontend::emit(app, "deep-link", url);
+    crate::frontend::emit(
+        app,
+        "deep-link-route",
+        DeepLinkEvent {
+            url: url.to_string(),
+            route,
+        },
+    );
+    show_main_window(app);
+}
+
+/// Bring the main window forward for a deep link
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.unminimize();
+    }
+}
+
+/// Setup deep link event handling
+pub fn setup(handle: &AppHandle) {
+    let app = handle.clone();
+
+    // tauri-plugin-deep-link emits "deep-link://new-url" events when a
+    // cushion:// URL is opened; in Tauri v2 the payload is a JSON array
+    handle.listen("deep-link://new-url", move |event| {
+        let payload = event.payload();
+        println!("Received deep link payload: {}", payload);
+
+        match serde_json::from_str::<Vec<String>>(payload) {
+            Ok(urls) => {
+                if let Some(url) = urls.first() {
+                    open(&app, url);
+                }
+            }
+            Err(e) => eprintln!("Failed to parse deep link payload: {}", e),
+        }
+    });
+
+    // A link that launched the app was handled before the listener above
+    // existed; route it now and it's held until the webview is ready
+    for url in launch_urls(handle) {
+        println!("🚀 Launched with deep link: {}", url);
+        open(handle, &url);
+    }
+}
+
+/// Deep links the app was launched with
+fn launch_urls(app: &AppHandle) -> Vec<String> {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    let mut urls: Vec<String> = match app.deep_link().get_current() {
+        Ok(urls) => urls
+            .unwrap_or_default()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        Err(e) => {
+            eprintln!("❌ Failed to get launch deep link: {}", e);
+            Vec::new()
+        }
+    };
+
+    // Linux desktop files pass the link as an argument, which the plugin
+    // only picks up for schemes registered at build time
+    #[cfg(target_os = "linux")]
+    {
+        let policy = app.state::<UrlPolicy>();
+        for arg in std::env::args().skip(1) {
+            if policy.is_deep_link(&arg) && !urls.contains(&arg) {
+                urls.push(arg);
+            }
+        }
+    }
+
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn web(route: Route) -> Option<DeepLink> {
+        Some(DeepLink::Web(route))
+    }
+
+    #[test]
+    fn test_routes() {
+        let policy = UrlPolicy::default();
+        let cases = [
+            (
+                "cushion://post/abc-123",
+                web(Route::Post {
+                    id: "abc-123".into(),
+                }),
+            ),
+            ("cushion://post/abc/", web(Route::Post { id: "abc".into() })),
+            ("cushion://POST/abc", web(Route::Post { id: "abc".into() })),
+            (
+                "cushion-dev://thread/t_1",
+                web(Route::Thread { id: "t_1".into() }),
+            ),
+            (
+                "cushion://user/u1?ref=mail",
+                web(Route::User { id: "u1".into() }),
+            ),
+            (
+                "cushion://workspace/acme",
+                web(Route::Workspace {
+                    slug: "acme".into(),
+                }),
+            ),
+            ("cushion://settings", web(Route::Settings { section: None })),
+            (
+                "cushion://settings/profile",
+                web(Route::Settings {
+                    section: Some("profile".into()),
+                }),
+            ),
+            (
+                "cushion://auth/callback?code=xyz&state=s1",
+                web(Route::AuthCallback {
+                    code: "xyz".into(),
+                    state: Some("s1".into()),
+                }),
+            ),
+            (
+                "cushion://auth/callback?code=a%2Bb",
+                web(Route::AuthCallback {
+                    code: "a+b".into(),
+                    state: None,
+                }),
+            ),
+            (
+                "cushion://auth/success?token=test&callbackUrl=%2Fdashboard",
+                web(Route::AuthSuccess {
+                    token: "test".into(),
+                    callback_url: Some("/dashboard".into()),
+                }),
+            ),
+            (
+                "cushion://auth/success?token=test",
+                web(Route::AuthSuccess {
+                    token: "test".into(),
+                    callback_url: None,
+                }),
+            ),
+            (
+                "cushion://update",
+                Some(DeepLink::Native(NativeRoute::Update)),
+            ),
+            // Left to the webview
+            ("cushion://chat/1", Some(DeepLink::Unrouted)),
+            ("cushion-dev://inbox", Some(DeepLink::Unrouted)),
+            (
+                "cushion://settings/notifications",
+                Some(DeepLink::Native(NativeRoute::NotificationSettings)),
+            ),
+            // Rejected
+            ("cushion://", None),
+            ("cushion://post", None),
+            ("cushion://post/1/2", None),
+            ("cushion://post/..%2F..%2Fetc", None),
+            ("cushion://post/%3Cscript%3E", None),
+            ("cushion://user/", None),
+            ("cushion://update/now", None),
+            ("cushion://auth/unknown", None),
+            ("cushion://auth/success?callbackUrl=%2Fdashboard", None),
+            (
+                "cushion://auth/success?token=t&callbackUrl=https%3A%2F%2Fevil.com",
+                None,
+            ),
+            (
+                "cushion://auth/success?token=t&callbackUrl=%2F%2Fevil.com",
+                None,
+            ),
+            (
+                "cushion://auth/success?token=t&callbackUrl=%2F%5Cevil.com",
+                None,
+            ),
+            (
+                "cushion://auth/success?token=t&callbackUrl=javascript%3Aalert(1)",
+                None,
+            ),
+            ("cushion://auth/callback", None),
+            ("cushion://auth/callback?code=", None),
+            ("https://app.cushion.so/post/1", None),
+            ("javascript:alert(1)", None),
+            ("not a url", None),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(parse(&policy, url).ok(), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_rejection_reasons() {
+        let policy = UrlPolicy::default();
+        let reason = |url| parse(&policy, url).unwrap_err();
+
+        assert_eq!(reason("cushion://update/now"), "unknown route 'update/now'");
+        assert_eq!(
+            reason("cushion://auth/success?token=t&callbackUrl=https%3A%2F%2Fevil.com"),
+            "callback 'https://evil.com' is not a path"
+        );
+        assert_eq!(reason("cushion://post"), "'post' takes exactly one id");
+        assert_eq!(reason("cushion://post/a.b"), "invalid id 'a.b'");
+        assert_eq!(reason("https://x.y"), "'https' is not a deep link scheme");
+    }
+
+    #[test]
+    fn test_ids_are_length_limited() {
+        let policy = UrlPolicy::default();
+        let long = "a".repeat(MAX_ID_LEN + 1);
+        assert!(parse(&policy, &format!("cushion://post/{}", &long[1..])).is_ok());
+        assert!(parse(&policy, &format!("cushion://post/{}", long)).is_err());
+    }
+
+    #[test]
+    fn test_route_payload_shape() {
+        let event = DeepLinkEvent {
+            url: "cushion://auth/callback?code=xyz".to_string(),
+            route: Route::AuthCallback {
+                code: "xyz".to_string(),
+                state: None,
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_value(event).unwrap(),
+            serde_json::json!({
+                "url": "cushion://auth/callback?code=xyz",
+                "route": { "type": "authCallback", "code": "xyz", "state": null }
+            })
+        );
+
+        let route = Route::AuthSuccess {
+            token: "t".to_string(),
+            callback_url: Some("/dashboard".to_string()),
+        };
+        assert_eq!(
+            serde_json::to_value(route).unwrap(),
+            serde_json::json!({ "type": "authSuccess", "token": "t", "callbackUrl": "/dashboard" })
+        );
+    }
+}
@@ -0,0 +1,261 @@
+//! Events for the webview that must not be lost while it loads
+//!
+//! A deep link that launched the app, or a notification clicked during
+//! startup, arrives before the web app has registered its listeners, and an
+//! event emitted then is dropped. Such events are sent through [`emit`],
+//! which holds them until the web app calls the `frontend_ready` command and
+//! then replays them in the order they arrived.
+//!
+//! The web app may not call `frontend_ready`, so the events are also
+//! replayed shortly after the page finishes loading. A reload or navigation
+//! starts holding events again.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::webview::{PageLoadEvent, PageLoadPayload};
+use tauri::{AppHandle, Emitter, Manager, Webview};
+
+/// How long after the page loads to wait for `frontend_ready` before
+/// replaying held events anyway, giving the app's scripts time to listen
+const READY_FALLBACK_DELAY: Duration = Duration::from_secs(3);
+
+/// An event waiting for the webview
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEvent {
+    pub name: &'static str,
+    pub payload: Value,
+}
+
+#[derive(Default)]
+struct Queue {
+    /// `None` while the webview is ready
+    held: Option<Vec<PendingEvent>>,
+    /// Counts page loads, so a fallback timer from an earlier load doesn't
+    /// release events held for a later one
+    load: u64,
+}
+
+/// Events held until the webview is ready
+pub struct PendingEvents {
+    queue: Mutex<Queue>,
+}
+
+impl PendingEvents {
+    pub fn new() -> Self {
+        Self {
+            queue: Mutex::new(Queue {
+                held: Some(Vec::new()),
+                load: 0,
+            }),
+        }
+    }
+
+    /// Start holding events again because the page is (re)loading
+    ///
+    /// Returns the load's number, for [`PendingEvents::ready_after_load`].
+    pub fn hold(&self) -> u64 {
+        let mut queue = self.queue.lock().unwrap();
+        queue.held.get_or_insert_with(Vec::new);
+        queue.load += 1;
+        queue.load
+    }
+
+    /// Hold `event` until the webview is ready, or pass it to `emit` now
+    ///
+    /// `emit` runs under the lock so nothing overtakes the replayed queue.
+    pub fn send(&self, event: PendingEvent, emit: impl FnOnce(PendingEvent)) {
+        let mut queue = self.queue.lock().unwrap();
+        match queue.held.as_mut() {
+            Some(pending) => {
+                println!("⏳ Holding {} until the webview is ready", event.name);
+                pending.push(event);
+            }
+            None => emit(event),
+        }
+    }
+
+    /// Number of the current page load, for [`PendingEvents::ready_after_load`]
+    pub fn current_load(&self) -> u64 {
+        self.queue.lock().unwrap().load
+    }
+
+    /// Mark the webview as ready, passing every held event to `emit` in order
+    ///
+    /// Returns how many events were replayed.
+    pub fn ready(&self, emit: impl FnMut(PendingEvent)) -> usize {
+        let mut queue = self.queue.lock().unwrap();
+        Self::release(&mut queue, emit)
+    }
+
+    /// Like [`PendingEvents::ready`], unless the page has started loading
+    /// again since load number `load`
+    pub fn ready_after_load(&self, load: u64, emit: impl FnMut(PendingEvent)) -> usize {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.load != load {
+            return 0;
+        }
+        Self::release(&mut queue, emit)
+    }
+
+    fn release(queue: &mut Queue, mut emit: impl FnMut(PendingEvent)) -> usize {
+        let pending = queue.held.take().unwrap_or_default();
+        let count = pending.len();
+        for event in pending {
+            emit(event);
+        }
+        count
+    }
+}
+
+impl Default for PendingEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Emit an event to the webview, holding it while the webview loads
+pub fn emit<S: Serialize>(app: &AppHandle, name: &'static str, payload: S) {
+    let payload = match serde_json::to_value(payload) {
+        Ok(payload) => payload,
+        Err(e) => {
+            eprintln!("❌ Failed to serialize {} event: {}", name, e);
+            return;
+        }
+    };
+
+    app.state::<PendingEvents>()
+        .send(PendingEvent { name, payload }, |event| {
+            let _ = app.emit(event.name, event.payload);
+        });
+}
+
+/// Replay the events held while the webview loaded
+pub fn ready(app: &AppHandle) {
+    let count = app.state::<PendingEvents>().ready(|event| {
+        let _ = app.emit(event.name, event.payload);
+    });
+    println!("✅ Webview ready, replayed {} held event(s)", count);
+}
+
+/// Hold events while the main webview loads, and replay them shortly after
+/// it finishes in case the web app never calls `frontend_ready`
+pub fn handle_page_load(webview: &Webview, payload: &PageLoadPayload<'_>) {
+    if webview.label() != "main" {
+        return;
+    }
+    let app = webview.app_handle().clone();
+
+    match payload.event() {
+        PageLoadEvent::Started => {
+            app.state::<PendingEvents>().hold();
+        }
+        PageLoadEvent::Finished => {
+            // The web app may already have called `frontend_ready`, so
+            // events keep flowing unless it still hasn't
+            let load = app.state::<PendingEvents>().current_load();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(READY_FALLBACK_DELAY).await;
+                let count = app
+                    .state::<PendingEvents>()
+                    .ready_after_load(load, |event| {
+                        let _ = app.emit(event.name, event.payload);
+                    });
+                if count > 0 {
+                    println!("✅ Page loaded, replayed {} held event(s)", count);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(name: &'static str, payload: &str) -> PendingEvent {
+        PendingEvent {
+            name,
+            payload: Value::String(payload.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_events_are_held_until_ready() {
+        let pending = PendingEvents::new();
+        let mut emitted = Vec::new();
+
+        pending.send(event("deep-link", "cushion://post/1"), |e| emitted.push(e));
+        pending.send(event("notification-action", "read"), |e| emitted.push(e));
+        pending.send(event("deep-link", "cushion://post/2"), |e| emitted.push(e));
+        assert!(emitted.is_empty());
+
+        assert_eq!(pending.ready(|e| emitted.push(e)), 3);
+        assert_eq!(
+            emitted,
+            vec![
+                event("deep-link", "cushion://post/1"),
+                event("notification-action", "read"),
+                event("deep-link", "cushion://post/2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_events_pass_through_once_ready() {
+        let pending = PendingEvents::new();
+        assert_eq!(pending.ready(|_| panic!("nothing was held")), 0);
+
+        let mut emitted = Vec::new();
+        pending.send(event("deep-link", "cushion://post/1"), |e| emitted.push(e));
+        assert_eq!(emitted, vec![event("deep-link", "cushion://post/1")]);
+
+        // Calling again replays nothing twice
+        assert_eq!(pending.ready(|_| panic!("already replayed")), 0);
+    }
+
+    #[test]
+    fn test_reload_holds_events_again() {
+        let pending = PendingEvents::new();
+        pending.ready(|_| {});
+
+        pending.hold();
+        let mut emitted = Vec::new();
+        pending.send(event("deep-link", "cushion://post/1"), |e| emitted.push(e));
+        assert!(emitted.is_empty());
+
+        assert_eq!(pending.ready(|e| emitted.push(e)), 1);
+        assert_eq!(emitted, vec![event("deep-link", "cushion://post/1")]);
+    }
+
+    #[test]
+    fn test_ready_before_load_finishes_stops_holding() {
+        let pending = PendingEvents::new();
+        let load = pending.hold();
+        assert_eq!(pending.ready(|_| panic!("nothing was held")), 0);
+
+        // The page finishes loading after the web app said it was ready
+        assert_eq!(pending.current_load(), load);
+        let mut emitted = Vec::new();
+        pending.send(event("deep-link", "cushion://post/1"), |e| emitted.push(e));
+        assert_eq!(emitted, vec![event("deep-link", "cushion://post/1")]);
+
+        assert_eq!(pending.ready_after_load(load, |_| panic!("already ready")), 0);
+    }
+
+    #[test]
+    fn test_fallback_replays_only_for_the_latest_load() {
+        let pending = PendingEvents::new();
+        let first = pending.hold();
+        pending.send(event("deep-link", "cushion://post/1"), |_| panic!("held"));
+
+        // The page reloads before the first load's fallback fires
+        let second = pending.hold();
+        assert_eq!(pending.ready_after_load(first, |_| panic!("stale")), 0);
+
+        let mut emitted = Vec::new();
+        assert_eq!(pending.ready_after_load(second, |e| emitted.push(e)), 1);
+        assert_eq!(emitted, vec![event("deep-link", "cushion://post/1")]);
+    }
+}
@@ -8,9 +8,18 @@ mod app_nap;
 mod updater;
 mod window;
 mod menu;
+mod storage;
+mod badge;
+mod url_policy;
+mod deep_link;
+mod frontend;
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+mod single_instance;
+#[cfg(target_os = "linux")]
+mod linux_bus;
 
 // Imports
-use tauri::{Emitter, Listener, Manager};
+use tauri::Manager;
 
 #[cfg(target_os = "macos")]
 use cocoa::base::id;
@@ -30,7 +39,21 @@ unsafe impl Sync for AppNapActivity {}
 /// Main Tauri application entry point
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    let context = tauri::generate_context!();
+
+    // Hand the launch to an already running instance and exit (Linux)
+    #[cfg(target_os = "linux")]
+    let primary = single_instance::acquire_or_exit(&context.config().identifier);
+
+    let builder = tauri::Builder::default();
+
+    // Must be registered first so a second instance exits before setup (Windows)
+    #[cfg(target_os = "windows")]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+        single_instance::on_second_launch(app, argv);
+    }));
+
+    builder
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
@@ -41,12 +64,47 @@ pub fn run() {
                 .build()
         )
         .manage(updater::PendingUpdate::new())
-        .setup(setup_app)
+        .manage(frontend::PendingEvents::new())
+        .setup(move |app| {
+            setup_app(app)?;
+
+            // Accept later launches once deep links can be routed
+            #[cfg(target_os = "linux")]
+            if let Some(primary) = primary {
+                single_instance::serve(app.handle(), primary);
+            }
+
+            Ok(())
+        })
         .on_window_event(window::handle_window_event)
+        .on_page_load(frontend::handle_page_load)
         .invoke_handler(tauri::generate_handler![
             commands::system::greet,
             commands::notification::show_notification,
+            commands::notification::get_notification_permission_status,
+            commands::notification::request_notification_permission,
             commands::notification::check_notification_permission,
+            commands::notification::get_notification_capabilities,
+            commands::notification::list_active_notifications,
+            commands::notification::clear_notifications_for_thread,
+            commands::notification::dismiss_notification,
+            commands::notification::dismiss_all_notifications,
+            commands::notification::get_delivered_notifications,
+            commands::notification::get_quiet_hours,
+            commands::notification::set_quiet_hours,
+            commands::notification::pause_notifications,
+            commands::notification::resume_notifications,
+            commands::notification::get_notification_batching,
+            commands::notification::set_notification_batching,
+            commands::notification::get_notification_history,
+            commands::notification::clear_notification_history,
+            commands::notification::get_notification_history_retention,
+            commands::notification::set_notification_history_retention,
+            commands::notification::get_notification_sounds,
+            commands::notification::set_notification_sounds,
+            commands::notification::schedule_notification,
+            commands::notification::cancel_scheduled_notification,
+            commands::notification::list_scheduled_notifications,
             commands::system::get_user_agent,
             commands::window::show_main_window,
             commands::window::is_window_visible,
@@ -54,11 +112,20 @@ pub fn run() {
             commands::window::is_window_minimized,
             commands::window::set_zoom_level,
             commands::system::open_url,
+            commands::system::frontend_ready,
+            commands::system::set_badge_count,
+            commands::system::clear_badge,
             commands::updater::check_for_updates,
             commands::updater::install_update,
             commands::updater::get_app_version,
+            commands::updater::get_update_state,
+            commands::updater::restart_to_update,
+            commands::updater::cancel_update_download,
+            commands::updater::get_update_channel,
+            commands::updater::get_available_update_channels,
+            commands::updater::set_update_channel,
         ])
-        .build(tauri::generate_context!())
+        .build(context)
         .expect("error while building tauri application")
         .run(window::handle_run_event);
 }
@@ -72,6 +139,9 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
         menu::setup_menu_events(app);
     }
 
+    // Load the trusted URL policy before anything that checks URLs
+    app.manage(url_policy::UrlPolicy::load(app.handle()));
+
     // Create the main window programmatically
     let win_builder = window::create_window_builder(app);
     let window = win_builder.build()?;
@@ -92,7 +162,7 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Setup deep link handling
-    setup_deep_links(app.handle());
+    deep_link::setup(app.handle());
 
     // Setup notification system
     notifications::setup(app.handle());
@@ -118,43 +188,3 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
-
-/// Setup deep link event handling
-fn setup_deep_links(handle: &tauri::AppHandle) {
-    let handle_clone = handle.clone();
-
-    // Set up deep link handler - tauri-plugin-deep-link emits "deep-link://new-url" events
-    // when a cushion:// URL is opened
-    handle.listen("deep-link://new-url", move |event| {
-        // In Tauri v2, payload is a String
-        let payload = event.payload();
-        println!("Received deep link payload: {}", payload);
-
-        // Try to parse as JSON array
-        if let Ok(urls) = serde_json::from_str::<Vec<String>>(payload) {
-            if let Some(url) = urls.first() {
-                // Validate the URL scheme before emitting
-                if let Ok(parsed) = url::Url::parse(url) {
-                    if parsed.scheme() == "cushion" || parsed.scheme() == "cushion-dev" {
-                        println!("Received deep link: {}", url);
-                        let _ = handle_clone.emit("deep-link", url);
-
-                        // Show the window when a deep link is received
-                        if let Some(window) = handle_clone.get_webview_window("main") {
-                            let _ = window.show();
-                            let _ = window.set_focus();
-                            let _ = window.unminimize();
-                        }
-                    } else {
-                        eprintln!(
-                            "Rejected deep link with invalid scheme: {}",
-                            parsed.scheme()
-                        );
-                    }
-                } else {
-                    eprintln!("Failed to parse deep link URL: {}", url);
-                }
-            }
-        }
-    });
-}
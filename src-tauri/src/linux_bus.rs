@@ -0,0 +1,18 @@
+//! Shared connection to the DBus session bus (Linux)
+//!
+//! Notifications and the launcher badge both talk to the session bus, so
+//! they share one connection instead of each opening their own.
+
+use std::sync::OnceLock;
+use zbus::blocking::Connection;
+
+static SESSION_BUS: OnceLock<Connection> = OnceLock::new();
+
+/// Get the shared session bus connection, connecting on first use
+pub fn session_bus() -> zbus::Result<Connection> {
+    if let Some(conn) = SESSION_BUS.get() {
+        return Ok(conn.clone());
+    }
+    let conn = Connection::session()?;
+    Ok(SESSION_BUS.get_or_init(|| conn).clone())
+}
@@ -2,14 +2,32 @@
 //!
 //! Creates the application menu bar for macOS.
 
-use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::Manager;
 
-use crate::updater;
+use crate::updater::{self, UpdateChannel};
+
+/// Menu item id prefix of the update channel choices
+const CHANNEL_ID_PREFIX: &str = "update-channel-";
+
+/// The update channel menu items, kept to re-check the current channel
+struct ChannelMenuItems(Vec<(UpdateChannel, CheckMenuItem<tauri::Wry>)>);
 
 /// Setup the application menu bar
 pub fn setup_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     let check_updates = MenuItem::with_id(app, "check-for-updates", "Check for Updates...", true, None::<&str>)?;
 
+    let current_channel = updater::current_channel(app.handle());
+    let mut channel_items = Vec::new();
+    for channel in updater::available_channels(app.handle()) {
+        let id = format!("{}{}", CHANNEL_ID_PREFIX, channel.label().to_lowercase());
+        let item = CheckMenuItem::with_id(app, id, channel.label(), true, channel == current_channel, None::<&str>)?;
+        channel_items.push((channel, item));
+    }
+    let channel_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> =
+        channel_items.iter().map(|(_, item)| item as _).collect();
+    let channel_submenu = Submenu::with_items(app, "Update Channel", true, &channel_refs)?;
+
     let app_submenu = Submenu::with_items(
         app,
         "Cushion",
@@ -18,6 +36,7 @@ pub fn setup_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
             &PredefinedMenuItem::about(app, Some("About Cushion"), None)?,
             &PredefinedMenuItem::separator(app)?,
             &check_updates,
+            &channel_submenu,
             &PredefinedMenuItem::separator(app)?,
             &PredefinedMenuItem::hide(app, Some("Hide Cushion"))?,
             &PredefinedMenuItem::hide_others(app, Some("Hide Others"))?,
@@ -66,6 +85,7 @@ pub fn setup_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
 
     let menu = Menu::with_items(app, &[&app_submenu, &edit_submenu, &view_submenu, &window_submenu])?;
     app.set_menu(menu)?;
+    app.manage(ChannelMenuItems(channel_items));
 
     Ok(())
 }
@@ -74,8 +94,40 @@ pub fn setup_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
 pub fn setup_menu_events(app: &mut tauri::App) {
     let handle = app.handle().clone();
     app.on_menu_event(move |_app, event| {
-        if event.id().as_ref() == "check-for-updates" {
+        let id = event.id().as_ref();
+        if id == "check-for-updates" {
             updater::show_update_dialog(&handle, true);
+        } else if let Some(channel) = channel_for_id(id) {
+            let app = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                match updater::switch_channel(&app, channel).await {
+                    // Check the new channel straight away
+                    Ok(true) => updater::show_update_dialog(&app, false),
+                    Ok(false) => {}
+                    Err(e) => eprintln!("❌ Failed to switch update channel: {}", e),
+                }
+                sync_channel_items(&app);
+            });
         }
     });
 }
+
+/// The update channel a menu item id selects
+fn channel_for_id(id: &str) -> Option<UpdateChannel> {
+    let name = id.strip_prefix(CHANNEL_ID_PREFIX)?;
+    UpdateChannel::ALL
+        .into_iter()
+        .find(|channel| channel.label().eq_ignore_ascii_case(name))
+}
+
+/// Check the current channel's menu item and uncheck the others
+///
+/// Clicking a check item toggles it, so this also undoes a cancelled switch.
+fn sync_channel_items(app: &tauri::AppHandle) {
+    let current = updater::current_channel(app);
+    if let Some(items) = app.try_state::<ChannelMenuItems>() {
+        for (channel, item) in &items.0 {
+            let _ = item.set_checked(*channel == current);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Platform notification backends
+//!
+//! `NotificationManager` does everything platform-independent (validation,
+//! quiet hours, batching, metadata, click routing) and hands the result to a
+//! [`NotificationBackend`]. macOS, Windows and Linux each have one; tests use
+//! [`RecordingBackend`], which records calls and simulates clicks.
+
+use super::{NotificationCapabilities, NotificationPermission, NotificationRequest};
+
+#[cfg(test)]
+use super::{ClickAction, NotificationClick, NotificationManager};
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// Shows and withdraws notifications on one platform
+pub trait NotificationBackend: Send + Sync {
+    /// Show a notification, using only features the platform supports
+    fn show(
+        &self,
+        request: &NotificationRequest,
+        capabilities: &NotificationCapabilities,
+    ) -> Result<(), String>;
+
+    /// Withdraw a shown notification
+    fn dismiss(&self, id: &str, thread_id: Option<&str>) -> Result<(), String>;
+
+    /// Withdraw all of our shown notifications
+    fn dismiss_all(&self) -> Result<(), String>;
+
+    /// Withdraw the notifications of a thread, `ids` being the ones we know of
+    fn clear_thread(&self, thread_id: &str, ids: &[String]) -> Result<(), String>;
+
+    /// Ids of our notifications the OS is still showing
+    fn delivered(&self) -> Result<Vec<String>, String>;
+
+    /// What the platform's notification system supports
+    fn capabilities(&self) -> Result<NotificationCapabilities, String>;
+
+    /// The notification permission status, without prompting the user
+    fn permission_status(&self) -> Result<NotificationPermission, String>;
+
+    /// Ask the user for permission to show notifications, on platforms with
+    /// a permission prompt
+    fn request_permission(&self) -> Result<NotificationPermission, String> {
+        self.permission_status()
+    }
+}
+
+/// Backend for platforms without notification support
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub struct UnsupportedBackend;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+impl NotificationBackend for UnsupportedBackend {
+    fn show(&self, _request: &NotificationRequest, _capabilities: &NotificationCapabilities) -> Result<(), String> {
+        Err("Notifications not supported on this platform".to_string())
+    }
+
+    fn dismiss(&self, _id: &str, _thread_id: Option<&str>) -> Result<(), String> {
+        Err("Notifications not supported on this platform".to_string())
+    }
+
+    fn dismiss_all(&self) -> Result<(), String> {
+        Err("Notifications not supported on this platform".to_string())
+    }
+
+    fn clear_thread(&self, _thread_id: &str, _ids: &[String]) -> Result<(), String> {
+        Err("Notifications not supported on this platform".to_string())
+    }
+
+    fn delivered(&self) -> Result<Vec<String>, String> {
+        Err("Notifications not supported on this platform".to_string())
+    }
+
+    fn capabilities(&self) -> Result<NotificationCapabilities, String> {
+        Ok(NotificationCapabilities::default())
+    }
+
+    fn permission_status(&self) -> Result<NotificationPermission, String> {
+        Ok(NotificationPermission::new(super::PermissionStatus::Denied))
+    }
+}
+
+/// A call made to a [`RecordingBackend`]
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackendCall {
+    Show(Box<NotificationRequest>),
+    Dismiss(String),
+    DismissAll,
+    ClearThread(String),
+}
+
+/// In-memory backend recording every call, for tests
+#[cfg(test)]
+pub struct RecordingBackend {
+    calls: Mutex<Vec<BackendCall>>,
+    capabilities: Result<NotificationCapabilities, String>,
+}
+
+#[cfg(test)]
+impl RecordingBackend {
+    pub fn new(capabilities: NotificationCapabilities) -> Self {
+        Self {
+            calls: Mutex::new(Vec::new()),
+            capabilities: Ok(capabilities),
+        }
+    }
+
+    /// A backend whose capabilities can't be probed, like a Linux session
+    /// without a notification server
+    pub fn without_capabilities(error: &str) -> Self {
+        Self {
+            calls: Mutex::new(Vec::new()),
+            capabilities: Err(error.to_string()),
+        }
+    }
+
+    /// Every call made so far, oldest first
+    pub fn calls(&self) -> Vec<BackendCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// The notifications shown so far, oldest first
+    pub fn shown(&self) -> Vec<NotificationRequest> {
+        self.calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                BackendCall::Show(request) => Some(*request),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Simulate the user interacting with a shown notification, the way the
+    /// platform backends report it to the manager
+    pub fn click(&self, manager: &NotificationManager, id: &str, action: ClickAction) {
+        assert!(
+            self.shown().iter().any(|request| request.id == id),
+            "notification {} was never shown",
+            id
+        );
+
+        manager.handle_click(NotificationClick {
+            id: id.to_string(),
+            url: manager.get_url(id),
+            action,
+        });
+    }
+
+    fn record(&self, call: BackendCall) -> Result<(), String> {
+        self.calls.lock().unwrap().push(call);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl NotificationBackend for RecordingBackend {
+    fn show(&self, request: &NotificationRequest, _capabilities: &NotificationCapabilities) -> Result<(), String> {
+        self.record(BackendCall::Show(Box::new(request.clone())))
+    }
+
+    fn dismiss(&self, id: &str, _thread_id: Option<&str>) -> Result<(), String> {
+        self.record(BackendCall::Dismiss(id.to_string()))
+    }
+
+    fn dismiss_all(&self) -> Result<(), String> {
+        self.record(BackendCall::DismissAll)
+    }
+
+    fn clear_thread(&self, thread_id: &str, _ids: &[String]) -> Result<(), String> {
+        self.record(BackendCall::ClearThread(thread_id.to_string()))
+    }
+
+    fn delivered(&self) -> Result<Vec<String>, String> {
+        Ok(self.shown().into_iter().map(|request| request.id).collect())
+    }
+
+    fn capabilities(&self) -> Result<NotificationCapabilities, String> {
+        self.capabilities.clone()
+    }
+
+    fn permission_status(&self) -> Result<NotificationPermission, String> {
+        Ok(NotificationPermission::new(super::PermissionStatus::Granted))
+    }
+}
@@ -0,0 +1,359 @@
+//! Notification batching
+//!
+//! Collects notifications arriving in a short burst, e.g. after reconnecting
+//! from sleep, and shows them as a single digest instead of a storm of
+//! notifications. The first notification of a burst is shown right away and
+//! opens a window; when it closes the notifications collected meanwhile are
+//! shown individually, or as a digest if the window saw at least `threshold`
+//! notifications in all. A digest never covers fewer than
+//! [`MIN_DIGEST_LEN`] notifications, so a `threshold` below 3 acts as 3.
+//!
+//! A notification folded into a digest is never shown by itself, so its id
+//! doesn't match anything the system displays: dismissing it does nothing,
+//! and it's missing from the delivered notifications. The digest has its own
+//! id.
+
+use super::{NotificationCategory, NotificationRequest};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+/// File in the app config directory holding the batching settings
+pub const SETTINGS_FILE: &str = "notification-batching.json";
+
+/// Longest batching window accepted
+pub const MAX_WINDOW_MS: u64 = 60_000;
+
+/// Fewest notifications folded into a digest; the window's first one was
+/// already shown, and a digest of a single notification would only repeat it
+const MIN_DIGEST_LEN: usize = 2;
+
+/// Maximum number of notification titles listed in a digest body
+const DIGEST_TITLES: usize = 5;
+
+/// User-configured batching settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BatchingSettings {
+    /// Whether notifications are batched at all
+    pub enabled: bool,
+    /// How long notifications are collected after the first one, in milliseconds
+    pub window_ms: u64,
+    /// Minimum number of notifications in a window to show a digest,
+    /// including the first one shown right away; below 3 it acts as 3
+    pub threshold: usize,
+}
+
+impl Default for BatchingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_ms: 2_000,
+            threshold: 3,
+        }
+    }
+}
+
+type DeliverFn = Arc<dyn Fn(NotificationRequest) + Send + Sync + 'static>;
+
+/// Aggregates bursts of notifications in front of the platform backends
+pub struct Batcher {
+    runtime: Handle,
+    settings: Mutex<BatchingSettings>,
+    /// Notifications collected in the open window, `None` if no window is open
+    pending: Arc<Mutex<Option<Vec<NotificationRequest>>>>,
+    deliver: DeliverFn,
+}
+
+impl Batcher {
+    /// Create a batcher whose windows run on `runtime` and which hands the
+    /// resulting notifications to `deliver`
+    pub fn new<F>(runtime: Handle, settings: BatchingSettings, deliver: F) -> Self
+    where
+        F: Fn(NotificationRequest) + Send + Sync + 'static,
+    {
+        Self {
+            runtime,
+            settings: Mutex::new(settings),
+            pending: Arc::new(Mutex::new(None)),
+            deliver: Arc::new(deliver),
+        }
+    }
+
+    /// The current settings
+    pub fn settings(&self) -> BatchingSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Replace the settings; a window that is already open keeps its timing
+    pub fn set_settings(&self, settings: BatchingSettings) {
+        *self.settings.lock().unwrap() = settings;
+    }
+
+    /// Show a notification right away and open a batching window, or
+    /// collect it if a window is already open
+    pub fn push(&self, request: NotificationRequest) {
+        let settings = self.settings();
+        if !settings.enabled {
+            (self.deliver)(request);
+            return;
+        }
+
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if let Some(batch) = pending.as_mut() {
+                // The open window will pick this one up
+                batch.push(request);
+                return;
+            }
+            *pending = Some(Vec::new());
+        }
+        (self.deliver)(request);
+
+        let pending = self.pending.clone();
+        let deliver = self.deliver.clone();
+        let window = Duration::from_millis(settings.window_ms);
+        self.runtime.spawn(async move {
+            tokio::time::sleep(window).await;
+
+            let batch = pending.lock().unwrap().take().unwrap_or_default();
+            close_window(batch, settings.threshold, &deliver);
+        });
+    }
+
+    /// Close the open window now, showing what it collected, so nothing is
+    /// lost when the app quits
+    pub fn flush(&self) {
+        let Some(batch) = self.pending.lock().unwrap().take() else {
+            return;
+        };
+        close_window(batch, self.settings().threshold, &self.deliver);
+    }
+}
+
+/// Show the notifications collected in a window, as a digest if there are
+/// enough
+fn close_window(batch: Vec<NotificationRequest>, threshold: usize, deliver: &DeliverFn) {
+    // The first notification of the window was already shown
+    if batch.len() >= MIN_DIGEST_LEN && batch.len() + 1 >= threshold {
+        let title = format!("{} new updates in Cushion", batch.len());
+        println!("📦 Batching {} notifications into a digest", batch.len());
+        deliver(digest(batch, title));
+    } else {
+        for request in batch {
+            deliver(request);
+        }
+    }
+}
+
+/// Combine several notifications into one digest notification
+///
+/// The body lists the first few titles, and the URLs of all notifications are
+/// kept so clicking the digest can hand them to the webview.
+pub fn digest(batch: Vec<NotificationRequest>, title: String) -> NotificationRequest {
+    let mut body: Vec<String> = batch
+        .iter()
+        .take(DIGEST_TITLES)
+        .map(|request| request.title.clone())
+        .collect();
+    if batch.len() > DIGEST_TITLES {
+        body.push(format!("and {} more", batch.len() - DIGEST_TITLES));
+    }
+
+    let mut urls: Vec<String> = Vec::new();
+    for url in batch.into_iter().filter_map(|request| request.url) {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    NotificationRequest {
+        id: format!("cushion-{}", uuid::Uuid::new_v4()),
+        title,
+        body: body.join("\n"),
+        // Link straight to the notifications' target if they all share one
+        url: if urls.len() == 1 { urls.first().cloned() } else { None },
+        digest_urls: urls,
+        category: Some(NotificationCategory::Digest),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(title: &str) -> NotificationRequest {
+        NotificationRequest {
+            id: format!("cushion-{}", title),
+            title: title.to_string(),
+            url: Some(format!("cushion://{}", title)),
+            ..Default::default()
+        }
+    }
+
+    fn batcher(settings: BatchingSettings) -> (Batcher, Arc<Mutex<Vec<NotificationRequest>>>) {
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let sink = delivered.clone();
+        let batcher = Batcher::new(Handle::current(), settings, move |request| {
+            sink.lock().unwrap().push(request);
+        });
+        (batcher, delivered)
+    }
+
+    /// Let paused time run forward, then let the woken window task finish
+    async fn advance(millis: u64) {
+        tokio::time::sleep(Duration::from_millis(millis)).await;
+        tokio::task::yield_now().await;
+    }
+
+    fn titles(delivered: &Mutex<Vec<NotificationRequest>>) -> Vec<String> {
+        delivered.lock().unwrap().iter().map(|r| r.title.clone()).collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_becomes_digest_after_window() {
+        let (batcher, delivered) = batcher(BatchingSettings::default());
+        for title in ["a", "b", "c", "d"] {
+            batcher.push(request(title));
+        }
+
+        // The first is shown right away, the rest wait for the window
+        assert_eq!(titles(&delivered), vec!["a"]);
+        advance(1_999).await;
+        assert_eq!(titles(&delivered), vec!["a"]);
+
+        advance(1).await;
+        let delivered = delivered.lock().unwrap();
+        assert_eq!(delivered.len(), 2);
+        assert_eq!(delivered[1].title, "3 new updates in Cushion");
+        assert_eq!(delivered[1].body, "b\nc\nd");
+        assert_eq!(
+            delivered[1].digest_urls,
+            vec!["cushion://b", "cushion://c", "cushion://d"]
+        );
+        assert!(delivered[1].url.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_lone_notification_is_not_delayed() {
+        let (batcher, delivered) = batcher(BatchingSettings::default());
+        batcher.push(request("a"));
+        assert_eq!(titles(&delivered), vec!["a"]);
+
+        advance(2_000).await;
+        assert_eq!(titles(&delivered), vec!["a"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_below_threshold_delivers_individually() {
+        let settings = BatchingSettings {
+            threshold: 4,
+            ..Default::default()
+        };
+        let (batcher, delivered) = batcher(settings);
+        for title in ["a", "b", "c"] {
+            batcher.push(request(title));
+        }
+        assert_eq!(titles(&delivered), vec!["a"]);
+
+        advance(2_000).await;
+        assert_eq!(titles(&delivered), vec!["a", "b", "c"]);
+
+        // Exactly at the threshold
+        for title in ["d", "e", "f", "g"] {
+            batcher.push(request(title));
+        }
+        advance(2_000).await;
+        assert_eq!(
+            titles(&delivered),
+            vec!["a", "b", "c", "d", "3 new updates in Cushion"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_threshold_below_three_acts_as_three() {
+        let settings = BatchingSettings {
+            threshold: 2,
+            ..Default::default()
+        };
+        let (batcher, delivered) = batcher(settings);
+        batcher.push(request("a"));
+        batcher.push(request("b"));
+        advance(2_000).await;
+        assert_eq!(titles(&delivered), vec!["a", "b"]);
+
+        for title in ["c", "d", "e"] {
+            batcher.push(request(title));
+        }
+        advance(2_000).await;
+        assert_eq!(
+            titles(&delivered),
+            vec!["a", "b", "c", "2 new updates in Cushion"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_window_reopens_after_flush() {
+        let settings = BatchingSettings {
+            window_ms: 500,
+            threshold: 2,
+            ..Default::default()
+        };
+        let (batcher, delivered) = batcher(settings);
+        batcher.push(request("a"));
+        advance(600).await;
+        assert_eq!(titles(&delivered), vec!["a"]);
+
+        batcher.push(request("b"));
+        batcher.push(request("c"));
+        batcher.push(request("d"));
+        advance(500).await;
+        assert_eq!(titles(&delivered), vec!["a", "b", "2 new updates in Cushion"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_flush_shows_the_open_window() {
+        let (batcher, delivered) = batcher(BatchingSettings::default());
+        for title in ["a", "b", "c"] {
+            batcher.push(request(title));
+        }
+
+        batcher.flush();
+        assert_eq!(titles(&delivered), vec!["a", "2 new updates in Cushion"]);
+
+        // The window's timer finds nothing left to show
+        advance(2_000).await;
+        assert_eq!(delivered.lock().unwrap().len(), 2);
+        batcher.flush();
+        assert_eq!(delivered.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_disabled_delivers_immediately() {
+        let settings = BatchingSettings {
+            enabled: false,
+            ..Default::default()
+        };
+        let (batcher, delivered) = batcher(settings);
+        batcher.push(request("a"));
+        assert_eq!(titles(&delivered), vec!["a"]);
+    }
+
+    #[test]
+    fn test_digest_lists_top_titles_and_unique_urls() {
+        let mut batch: Vec<NotificationRequest> =
+            (1..=7).map(|n| request(&format!("message-{}", n))).collect();
+        batch.push(request("message-1"));
+
+        let summary = digest(batch, "8 new updates in Cushion".to_string());
+        assert!(summary.body.ends_with("message-5\nand 3 more"));
+        assert_eq!(summary.digest_urls.len(), 7);
+        assert_eq!(summary.category, Some(NotificationCategory::Digest));
+
+        let same_url = vec![request("a"), request("a")];
+        let summary = digest(same_url, "2 new updates in Cushion".to_string());
+        assert_eq!(summary.url.as_deref(), Some("cushion://a"));
+    }
+}
@@ -0,0 +1,166 @@
+//! What the platform's notification system can display
+//!
+//! macOS and Windows support every feature the backends use, but Linux
+//! notification servers differ widely, so their capabilities are probed over
+//! DBus with `GetServerInformation` and `GetCapabilities`.
+
+use serde::Serialize;
+
+/// Server capability for action buttons
+#[cfg(any(target_os = "linux", test))]
+const ACTIONS_CAPABILITY: &str = "actions";
+
+/// Server capability for `<b>`, `<i>`, `<u>` and `<a>` markup in the body
+#[cfg(any(target_os = "linux", test))]
+const BODY_MARKUP_CAPABILITY: &str = "body-markup";
+
+/// Server capabilities for showing an image or icon
+#[cfg(any(target_os = "linux", test))]
+const IMAGE_CAPABILITIES: &[&str] = &["icon-static", "icon-multi"];
+
+/// Server capability for keeping notifications until dismissed
+#[cfg(any(target_os = "linux", test))]
+const PERSISTENCE_CAPABILITY: &str = "persistence";
+
+/// Server capability for inline replies (KDE Plasma extension)
+#[cfg(any(target_os = "linux", test))]
+const INLINE_REPLY_CAPABILITY: &str = "inline-reply";
+
+/// Server capability for playing sounds
+#[cfg(any(target_os = "linux", test))]
+const SOUND_CAPABILITY: &str = "sound";
+
+/// Name and version of a Linux notification server
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInformation {
+    pub name: String,
+    pub vendor: String,
+    pub version: String,
+    /// Version of the notification spec the server implements
+    pub spec_version: String,
+}
+
+/// Features the notification system supports
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationCapabilities {
+    /// The notification server (Linux)
+    pub server: Option<ServerInformation>,
+    /// Clicks and action buttons are reported back
+    pub actions: bool,
+    /// The body is rendered as markup rather than plain text
+    pub body_markup: bool,
+    /// Avatars and attachment images are shown
+    pub images: bool,
+    /// Notifications stay in a notification center until dismissed
+    pub persistence: bool,
+    /// Replies can be typed into the notification
+    pub inline_reply: bool,
+    /// Notification sounds are played
+    pub sound: bool,
+    /// Capability strings reported by the server (Linux)
+    pub reported: Vec<String>,
+}
+
+impl NotificationCapabilities {
+    /// Capabilities of the macOS and Windows backends
+    #[cfg(not(target_os = "linux"))]
+    pub fn native() -> Self {
+        Self {
+            server: None,
+            actions: true,
+            body_markup: false,
+            images: true,
+            persistence: true,
+            inline_reply: true,
+            sound: true,
+            reported: Vec::new(),
+        }
+    }
+
+    /// Capabilities assumed when the backend can't be asked
+    ///
+    /// Nearly every notification server supports actions, so clicks and
+    /// buttons are still sent; everything else falls back to plain text.
+    pub fn assumed() -> Self {
+        Self {
+            actions: true,
+            ..Default::default()
+        }
+    }
+
+    /// Capabilities of a Linux notification server, from its
+    /// `GetCapabilities` response
+    #[cfg(any(target_os = "linux", test))]
+    pub fn from_server(server: ServerInformation, reported: Vec<String>) -> Self {
+        let has = |capability: &str| reported.iter().any(|c| c == capability);
+
+        Self {
+            actions: has(ACTIONS_CAPABILITY),
+            body_markup: has(BODY_MARKUP_CAPABILITY),
+            images: IMAGE_CAPABILITIES.iter().any(|capability| has(capability)),
+            persistence: has(PERSISTENCE_CAPABILITY),
+            inline_reply: has(INLINE_REPLY_CAPABILITY),
+            sound: has(SOUND_CAPABILITY),
+            server: Some(server),
+            reported,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(name: &str) -> ServerInformation {
+        ServerInformation {
+            name: name.to_string(),
+            vendor: "Test".to_string(),
+            version: "1.0".to_string(),
+            spec_version: "1.2".to_string(),
+        }
+    }
+
+    fn reported(capabilities: &[&str]) -> Vec<String> {
+        capabilities.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_gnome_shell_capabilities() {
+        let capabilities = NotificationCapabilities::from_server(
+            server("gnome-shell"),
+            reported(&["actions", "body", "body-markup", "icon-static", "persistence", "sound"]),
+        );
+
+        assert!(capabilities.actions);
+        assert!(capabilities.body_markup);
+        assert!(capabilities.images);
+        assert!(capabilities.persistence);
+        assert!(capabilities.sound);
+        assert!(!capabilities.inline_reply);
+        assert_eq!(capabilities.server.unwrap().name, "gnome-shell");
+    }
+
+    #[test]
+    fn test_kde_supports_inline_reply() {
+        let capabilities = NotificationCapabilities::from_server(
+            server("Plasma"),
+            reported(&["body", "body-hyperlinks", "body-markup", "icon-static", "actions", "inline-reply"]),
+        );
+
+        assert!(capabilities.inline_reply);
+        assert!(!capabilities.sound);
+    }
+
+    #[test]
+    fn test_minimal_server_is_plain_text_only() {
+        let capabilities =
+            NotificationCapabilities::from_server(server("notify-osd"), reported(&["body"]));
+
+        assert!(!capabilities.actions);
+        assert!(!capabilities.body_markup);
+        assert!(!capabilities.images);
+        assert_eq!(capabilities.reported, vec!["body"]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Notification categories for macOS action buttons
+//!
+//! macOS attaches buttons and the reply field to a notification through its
+//! category, and `setNotificationCategories:` replaces every registered
+//! category at once. Each set of buttons gets a category named after a hash
+//! of its actions, and only the [`MAX_CATEGORIES`] most recently used ones
+//! stay registered, so the set registered with every new combination stays
+//! small. A delivered notification whose category was evicted loses its
+//! buttons.
+
+use super::{NotificationAction, NotificationReply, REPLY_ACTION_ID};
+
+/// Most categories kept registered at once
+pub const MAX_CATEGORIES: usize = 32;
+
+/// The action buttons and reply field of a notification category
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryActions {
+    pub actions: Vec<NotificationAction>,
+    pub reply: Option<NotificationReply>,
+}
+
+impl CategoryActions {
+    /// Category identifier, the same for every notification with these
+    /// actions, in this run and the next
+    pub fn identifier(&self) -> String {
+        let mut parts: Vec<String> = self
+            .actions
+            .iter()
+            .map(|action| format!("{}={}", action.id, action.label))
+            .collect();
+        if let Some(ref reply) = self.reply {
+            parts.push(format!(
+                "{}={}/{}",
+                REPLY_ACTION_ID,
+                reply.label,
+                reply.placeholder.as_deref().unwrap_or_default()
+            ));
+        }
+        format!(
+            "cushion-actions-{:016x}",
+            fnv1a(parts.join("\n").as_bytes())
+        )
+    }
+}
+
+/// 64-bit FNV-1a, a hash that doesn't change between runs or Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The registered categories, least recently used first
+#[derive(Default)]
+pub struct CategoryRegistry {
+    categories: Vec<(String, CategoryActions)>,
+}
+
+impl CategoryRegistry {
+    pub const fn new() -> Self {
+        Self {
+            categories: Vec::new(),
+        }
+    }
+
+    /// Mark a category as used, adding it if needed
+    ///
+    /// Returns its identifier, and whether the registered set changed and
+    /// has to be registered again.
+    pub fn use_category(&mut self, category: CategoryActions) -> (String, bool) {
+        let identifier = category.identifier();
+        if let Some(index) = self.categories.iter().position(|(id, _)| *id == identifier) {
+            let used = self.categories.remove(index);
+            self.categories.push(used);
+            return (identifier, false);
+        }
+
+        if self.categories.len() >= MAX_CATEGORIES {
+            let (evicted, _) = self.categories.remove(0);
+            println!("🍎 Dropping notification category: {}", evicted);
+        }
+        self.categories.push((identifier.clone(), category));
+        (identifier, true)
+    }
+
+    /// The registered categories and their identifiers
+    pub fn categories(&self) -> impl Iterator<Item = (&str, &CategoryActions)> {
+        self.categories
+            .iter()
+            .map(|(identifier, category)| (identifier.as_str(), category))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(labels: &[&str]) -> CategoryActions {
+        CategoryActions {
+            actions: labels
+                .iter()
+                .map(|label| NotificationAction {
+                    id: label.to_lowercase(),
+                    label: label.to_string(),
+                })
+                .collect(),
+            reply: None,
+        }
+    }
+
+    #[test]
+    fn test_identifier_depends_only_on_the_actions() {
+        let read = category(&["Read"]);
+        assert_eq!(read.identifier(), category(&["Read"]).identifier());
+        assert_ne!(
+            read.identifier(),
+            category(&["Read", "Snooze"]).identifier()
+        );
+        assert!(read.identifier().starts_with("cushion-actions-"));
+
+        let with_reply = CategoryActions {
+            reply: Some(NotificationReply {
+                label: "Reply".to_string(),
+                placeholder: None,
+            }),
+            ..read.clone()
+        };
+        assert_ne!(read.identifier(), with_reply.identifier());
+    }
+
+    #[test]
+    fn test_registry_is_bounded_and_keeps_recent_categories() {
+        let mut registry = CategoryRegistry::new();
+        let (first, changed) = registry.use_category(category(&["First"]));
+        assert!(changed);
+        for n in 1..MAX_CATEGORIES {
+            registry.use_category(category(&[&format!("Button {}", n)]));
+        }
+
+        // Using a registered category needs no new registration, and keeps
+        // it from being evicted next
+        assert_eq!(
+            registry.use_category(category(&["First"])),
+            (first.clone(), false)
+        );
+        let (_, changed) = registry.use_category(category(&["One more"]));
+        assert!(changed);
+
+        let identifiers: Vec<&str> = registry.categories().map(|(id, _)| id).collect();
+        assert_eq!(identifiers.len(), MAX_CATEGORIES);
+        assert!(identifiers.contains(&first.as_str()));
+        assert!(!identifiers.contains(&category(&["Button 1"]).identifier().as_str()));
+    }
+}
@@ -0,0 +1,280 @@
+//! Notification history log
+//!
+//! Every notification the webview asks for is recorded along with what the
+//! user did with it, including ones held during quiet hours or folded into a
+//! digest, so the webview can list past notifications even after the OS has
+//! dropped them. The log lives in a versioned JSON file in the app data directory and
+//! is pruned according to the user's retention settings.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File in the app data directory holding the history log
+pub const HISTORY_FILE: &str = "notification-history.json";
+
+/// File in the app config directory holding the retention settings
+pub const RETENTION_FILE: &str = "notification-history-retention.json";
+
+/// Current version of the history file format
+const HISTORY_VERSION: u32 = 1;
+
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// What the user did with a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryOutcome {
+    /// Still shown, or dropped by the OS without user interaction
+    Shown,
+    /// Clicked, or acted on through a button or reply
+    Clicked,
+    /// Dismissed by the user
+    Dismissed,
+}
+
+/// A notification recorded in the history log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    pub url: Option<String>,
+    /// When the notification was shown, in milliseconds since the Unix epoch
+    pub shown_at: u64,
+    pub outcome: HistoryOutcome,
+}
+
+/// How long history entries are kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryRetention {
+    /// Entries older than this many days are removed
+    pub max_age_days: u32,
+    /// Only the newest entries up to this count are kept
+    pub max_entries: usize,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self {
+            max_age_days: 30,
+            max_entries: 1_000,
+        }
+    }
+}
+
+/// On-disk format of the history log
+#[derive(Serialize, Deserialize)]
+struct HistoryFile {
+    version: u32,
+    entries: Vec<HistoryEntry>,
+}
+
+/// The history log, oldest entry first
+#[derive(Clone, Default)]
+pub struct NotificationHistory {
+    entries: Vec<HistoryEntry>,
+    retention: HistoryRetention,
+}
+
+impl NotificationHistory {
+    pub fn new(entries: Vec<HistoryEntry>, retention: HistoryRetention) -> Self {
+        Self { entries, retention }
+    }
+
+    /// Load the log from disk, starting empty if it's missing or unreadable
+    ///
+    /// A file that can't be read is moved aside rather than overwritten, so
+    /// history written by a newer version of the app isn't lost.
+    pub fn load(path: &Path, retention: HistoryRetention) -> Self {
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return Self::new(Vec::new(), retention);
+        };
+
+        match parse(&contents) {
+            Ok(entries) => Self::new(entries, retention),
+            Err(e) => {
+                eprintln!("⚠️ Ignoring notification history: {}", e);
+                let _ = std::fs::rename(path, path.with_extension("json.bak"));
+                Self::new(Vec::new(), retention)
+            }
+        }
+    }
+
+    /// Save the log to disk
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        crate::storage::write_json(
+            path,
+            &HistoryFile {
+                version: HISTORY_VERSION,
+                entries: self.entries.clone(),
+            },
+        )
+    }
+
+    /// Record a shown notification, pruning old entries
+    pub fn record(&mut self, entry: HistoryEntry, now: u64) {
+        self.entries.push(entry);
+        self.prune(now);
+    }
+
+    /// Record what the user did with a notification
+    ///
+    /// Returns whether the notification was found in the log.
+    pub fn set_outcome(&mut self, id: &str, outcome: HistoryOutcome) -> bool {
+        match self.entries.iter_mut().rev().find(|entry| entry.id == id) {
+            Some(entry) => {
+                entry.outcome = outcome;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Search the log, newest first
+    ///
+    /// The query matches the title or body, ignoring case; an empty query
+    /// matches everything.
+    pub fn search(&self, query: &str, limit: usize, offset: usize) -> Vec<HistoryEntry> {
+        let query = query.trim().to_lowercase();
+
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| {
+                query.is_empty()
+                    || entry.title.to_lowercase().contains(&query)
+                    || entry.body.to_lowercase().contains(&query)
+            })
+            .skip(offset)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Remove every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The current retention settings
+    pub fn retention(&self) -> &HistoryRetention {
+        &self.retention
+    }
+
+    /// Replace the retention settings, pruning entries they no longer allow
+    pub fn set_retention(&mut self, retention: HistoryRetention, now: u64) {
+        self.retention = retention;
+        self.prune(now);
+    }
+
+    fn prune(&mut self, now: u64) {
+        let max_age = u64::from(self.retention.max_age_days) * MILLIS_PER_DAY;
+        self.entries
+            .retain(|entry| now.saturating_sub(entry.shown_at) < max_age);
+
+        let excess = self.entries.len().saturating_sub(self.retention.max_entries);
+        self.entries.drain(..excess);
+    }
+}
+
+/// Parse a history file, checking its format version
+fn parse(contents: &str) -> Result<Vec<HistoryEntry>, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(contents).map_err(|e| format!("invalid JSON: {}", e))?;
+
+    match value.get("version").and_then(|version| version.as_u64()) {
+        Some(version) if version == u64::from(HISTORY_VERSION) => {
+            let file: HistoryFile =
+                serde_json::from_value(value).map_err(|e| format!("invalid history: {}", e))?;
+            Ok(file.entries)
+        }
+        Some(version) => Err(format!("unsupported history version {}", version)),
+        None => Err("missing history version".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, title: &str, shown_at: u64) -> HistoryEntry {
+        HistoryEntry {
+            id: id.to_string(),
+            title: title.to_string(),
+            body: format!("{} body", title),
+            url: None,
+            shown_at,
+            outcome: HistoryOutcome::Shown,
+        }
+    }
+
+    fn ids(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_records_outcomes() {
+        let mut history = NotificationHistory::default();
+        history.record(entry("a", "Hello", 0), 0);
+
+        assert!(history.set_outcome("a", HistoryOutcome::Clicked));
+        assert!(!history.set_outcome("missing", HistoryOutcome::Dismissed));
+        assert_eq!(history.search("", 10, 0)[0].outcome, HistoryOutcome::Clicked);
+    }
+
+    #[test]
+    fn test_search_is_newest_first_and_paginated() {
+        let mut history = NotificationHistory::default();
+        history.record(entry("a", "Design review", 1), 1);
+        history.record(entry("b", "Lunch", 2), 2);
+        history.record(entry("c", "design sync", 3), 3);
+        history.record(entry("d", "Standup", 4), 4);
+
+        assert_eq!(ids(&history.search("", 2, 0)), vec!["d", "c"]);
+        assert_eq!(ids(&history.search("", 2, 2)), vec!["b", "a"]);
+        assert_eq!(ids(&history.search(" DESIGN ", 10, 0)), vec!["c", "a"]);
+        assert_eq!(ids(&history.search("lunch body", 10, 0)), vec!["b"]);
+        assert!(history.search("design", 10, 5).is_empty());
+    }
+
+    #[test]
+    fn test_retention_prunes_by_age_and_count() {
+        let retention = HistoryRetention {
+            max_age_days: 1,
+            max_entries: 2,
+        };
+        let mut history = NotificationHistory::new(Vec::new(), retention);
+        history.record(entry("old", "Old", 0), 0);
+        history.record(entry("a", "A", MILLIS_PER_DAY), MILLIS_PER_DAY);
+        assert_eq!(ids(&history.search("", 10, 0)), vec!["a"]);
+
+        history.record(entry("b", "B", MILLIS_PER_DAY + 1), MILLIS_PER_DAY + 1);
+        history.record(entry("c", "C", MILLIS_PER_DAY + 2), MILLIS_PER_DAY + 2);
+        assert_eq!(ids(&history.search("", 10, 0)), vec!["c", "b"]);
+
+        history.set_retention(
+            HistoryRetention {
+                max_age_days: 30,
+                max_entries: 1,
+            },
+            MILLIS_PER_DAY + 2,
+        );
+        assert_eq!(ids(&history.search("", 10, 0)), vec!["c"]);
+    }
+
+    #[test]
+    fn test_parse_checks_version() {
+        let file = HistoryFile {
+            version: HISTORY_VERSION,
+            entries: vec![entry("a", "Hello", 1)],
+        };
+        let json = serde_json::to_string(&file).unwrap();
+        assert_eq!(ids(&parse(&json).unwrap()), vec!["a"]);
+
+        assert!(parse(r#"{"version": 99, "entries": []}"#).is_err());
+        assert!(parse(r#"{"entries": []}"#).is_err());
+        assert!(parse("not json").is_err());
+    }
+}
@@ -0,0 +1,327 @@
+//! Notification images
+//!
+//! The webview passes images (avatars, attachment thumbnails) as data URIs or
+//! local file paths; remote images are never fetched here. Images are
+//! validated and copied into a cache directory, because the platform backends
+//! need a file of their own: macOS moves attachments into its own store, and
+//! Windows and Linux read the file while the notification is shown.
+//!
+//! Since showing a notification can use up its image, a notification
+//! scheduled for later, or kept to be snoozed, gets its own copy.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Directory in the app cache directory holding notification images
+pub const CACHE_DIR: &str = "notification-images";
+
+/// How long cached images are kept
+pub const CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Largest image accepted
+const MAX_IMAGE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// How an image is shown on the notification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageKind {
+    /// A person's avatar, shown in place of the app icon where supported
+    #[default]
+    Avatar,
+    /// An attachment thumbnail, shown inline with the notification
+    Attachment,
+}
+
+/// An image passed by the webview
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageInput {
+    /// `data:image/...;base64,...` URI or absolute local file path
+    pub source: String,
+    #[serde(default)]
+    pub kind: ImageKind,
+}
+
+/// A validated image in the cache, ready for the platform backends
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationImage {
+    pub path: PathBuf,
+    pub kind: ImageKind,
+}
+
+/// Image formats every platform backend can display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+}
+
+impl ImageFormat {
+    /// Detect the format from the file's magic bytes
+    fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else {
+            None
+        }
+    }
+
+    fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Gif => "gif",
+        }
+    }
+}
+
+/// Validate an image and write it to the cache directory
+pub fn cache_image(cache_dir: &Path, input: &ImageInput) -> Result<NotificationImage, String> {
+    let (bytes, declared_type) = read_source(&input.source)?;
+
+    let format = ImageFormat::sniff(&bytes)
+        .ok_or_else(|| "Notification images must be PNG, JPEG or GIF".to_string())?;
+    if let Some(declared_type) = declared_type {
+        if !declared_type.eq_ignore_ascii_case(format.mime_type()) {
+            return Err(format!(
+                "Notification image is declared as {} but contains {}",
+                declared_type,
+                format.mime_type()
+            ));
+        }
+    }
+
+    fs::create_dir_all(cache_dir)
+        .map_err(|e| format!("Failed to create {}: {}", cache_dir.display(), e))?;
+    let path = cache_dir.join(format!("{}.{}", uuid::Uuid::new_v4(), format.extension()));
+    fs::write(&path, bytes).map_err(|e| format!("Failed to cache notification image: {}", e))?;
+
+    Ok(NotificationImage {
+        path,
+        kind: input.kind,
+    })
+}
+
+/// Copy a cached image to a new file in the cache, for a notification
+/// shown later
+pub fn copy_image(cache_dir: &Path, image: &NotificationImage) -> Result<NotificationImage, String> {
+    let extension = image
+        .path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    let path = cache_dir.join(format!("{}.{}", uuid::Uuid::new_v4(), extension));
+    fs::copy(&image.path, &path).map_err(|e| format!("Failed to copy notification image: {}", e))?;
+
+    Ok(NotificationImage {
+        path,
+        kind: image.kind,
+    })
+}
+
+/// Remove cached images older than `max_age`, except those in `keep`
+pub fn prune_cache(cache_dir: &Path, max_age: Duration, now: SystemTime, keep: &[PathBuf]) {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        if keep.contains(&entry.path()) {
+            continue;
+        }
+        let expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .map(|modified| now.duration_since(modified).unwrap_or_default() >= max_age)
+            .unwrap_or(false);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Read the image bytes, along with the MIME type declared by a data URI
+fn read_source(source: &str) -> Result<(Vec<u8>, Option<String>), String> {
+    if let Some(data) = source.strip_prefix("data:") {
+        return decode_data_uri(data).map(|(bytes, mime)| (bytes, Some(mime)));
+    }
+
+    let path = if Path::new(source).is_absolute() {
+        PathBuf::from(source)
+    } else if source.starts_with("file://") {
+        url::Url::parse(source)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+            .ok_or_else(|| format!("Invalid notification image path: {}", source))?
+    } else {
+        // Never fetch remote images; the webview downloads them itself
+        return Err("Notification images must be a data URI or an absolute file path".to_string());
+    };
+
+    let metadata = fs::metadata(&path)
+        .map_err(|e| format!("Failed to read notification image {}: {}", path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(format!("Notification image {} is not a file", path.display()));
+    }
+    if metadata.len() > MAX_IMAGE_BYTES {
+        return Err(too_large());
+    }
+
+    let bytes = fs::read(&path)
+        .map_err(|e| format!("Failed to read notification image {}: {}", path.display(), e))?;
+    Ok((bytes, None))
+}
+
+/// Decode the part of a data URI after `data:`
+fn decode_data_uri(data: &str) -> Result<(Vec<u8>, String), String> {
+    let (header, encoded) = data
+        .split_once(',')
+        .ok_or_else(|| "Invalid notification image data URI".to_string())?;
+    let mime_type = header
+        .strip_suffix(";base64")
+        .ok_or_else(|| "Notification image data URIs must be base64 encoded".to_string())?;
+
+    // Reject oversized images before decoding them
+    if encoded.len() as u64 > MAX_IMAGE_BYTES.div_ceil(3) * 4 {
+        return Err(too_large());
+    }
+
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Invalid notification image data: {}", e))?;
+    if bytes.len() as u64 > MAX_IMAGE_BYTES {
+        return Err(too_large());
+    }
+
+    Ok((bytes, mime_type.to_string()))
+}
+
+fn too_large() -> String {
+    format!(
+        "Notification images must be at most {} KB",
+        MAX_IMAGE_BYTES / 1024
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("cushion-images-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn input(source: String) -> ImageInput {
+        ImageInput {
+            source,
+            kind: ImageKind::Attachment,
+        }
+    }
+
+    #[test]
+    fn test_caches_data_uri() {
+        let dir = temp_dir();
+        let source = format!("data:image/png;base64,{}", STANDARD.encode(PNG));
+
+        let image = cache_image(&dir, &input(source)).unwrap();
+        assert_eq!(image.kind, ImageKind::Attachment);
+        assert_eq!(image.path.extension().unwrap(), "png");
+        assert_eq!(fs::read(&image.path).unwrap(), PNG);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_copies_local_file() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let original = dir.join("avatar.jpg");
+        fs::write(&original, b"\xFF\xD8\xFF\xE0 jpeg").unwrap();
+
+        let image = cache_image(&dir.join("cache"), &input(original.display().to_string())).unwrap();
+        assert_ne!(image.path, original);
+        assert_eq!(image.path.extension().unwrap(), "jpg");
+        assert!(original.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_invalid_images() {
+        let dir = temp_dir();
+
+        // Remote and relative sources are never read
+        assert!(cache_image(&dir, &input("https://cushion.so/avatar.png".to_string())).is_err());
+        assert!(cache_image(&dir, &input("avatar.png".to_string())).is_err());
+
+        // Content must match a supported format and the declared type
+        let svg = format!("data:image/svg+xml;base64,{}", STANDARD.encode("<svg/>"));
+        assert!(cache_image(&dir, &input(svg)).is_err());
+        let mislabeled = format!("data:image/gif;base64,{}", STANDARD.encode(PNG));
+        assert!(cache_image(&dir, &input(mislabeled)).is_err());
+        assert!(cache_image(&dir, &input("data:image/png,raw".to_string())).is_err());
+
+        let mut huge = PNG.to_vec();
+        huge.resize(MAX_IMAGE_BYTES as usize + 1, 0);
+        let huge = format!("data:image/png;base64,{}", STANDARD.encode(huge));
+        assert!(cache_image(&dir, &input(huge)).is_err());
+
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_prune_removes_expired_images() {
+        let dir = temp_dir();
+        let source = format!("data:image/png;base64,{}", STANDARD.encode(PNG));
+        let image = cache_image(&dir, &input(source)).unwrap();
+
+        prune_cache(&dir, CACHE_TTL, SystemTime::now(), &[]);
+        assert!(image.path.exists());
+
+        prune_cache(&dir, CACHE_TTL, SystemTime::now() + CACHE_TTL, &[]);
+        assert!(!image.path.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_copies_survive_the_original() {
+        let dir = temp_dir();
+        let source = format!("data:image/png;base64,{}", STANDARD.encode(PNG));
+        let image = cache_image(&dir, &input(source)).unwrap();
+
+        let copy = copy_image(&dir, &image).unwrap();
+        assert_ne!(copy.path, image.path);
+        assert_eq!(copy.path.extension().unwrap(), "png");
+
+        // Showing the original may move it away
+        fs::remove_file(&image.path).unwrap();
+        assert_eq!(fs::read(&copy.path).unwrap(), PNG);
+
+        // Images still needed by scheduled notifications are kept
+        let keep = [copy.path.clone()];
+        prune_cache(&dir, CACHE_TTL, SystemTime::now() + CACHE_TTL, &keep);
+        assert!(copy.path.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,72 +1,831 @@
 /// Linux notification implementation using notify-rust
 ///
 /// This provides native freedesktop/XDG notifications with click handling
-/// via DBus action callbacks.
+/// via a DBus listener for the server's `ActionInvoked`,
+/// `NotificationReplied` and `NotificationClosed` signals.
 
-use super::{ClickAction, NotificationClick, NotificationManager};
+use super::{
+    ClickAction, NotificationCapabilities, NotificationClick, NotificationManager,
+    NotificationPermission, NotificationRequest,
+};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 #[cfg(target_os = "linux")]
-use notify_rust::{Notification, Timeout};
+use super::{
+    backend::NotificationBackend,
+    capabilities::ServerInformation,
+    markup::{render_body, BodyFormat},
+    sounds::bundled_file,
+    NotificationPriority, NotificationSound, PermissionStatus, DEFAULT_ACTION_ID, REPLY_ACTION_ID,
+};
+
+#[cfg(target_os = "linux")]
+use notify_rust::{Hint, Notification, Timeout, Urgency};
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(target_os = "linux")]
+use crate::linux_bus::session_bus;
+
+/// DBus name and interface implemented by freedesktop notification servers
+#[cfg(target_os = "linux")]
+const NOTIFICATIONS_INTERFACE: &str = "org.freedesktop.Notifications";
+
+/// DBus object path of the notification server
+#[cfg(target_os = "linux")]
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+/// Longest body sent, in graphemes; servers show a few lines at most and
+/// some reject very long bodies
+#[cfg(target_os = "linux")]
+const MAX_BODY_LEN: usize = 300;
+
+/// Hint some servers (notify-osd, GNOME) use to update a notification in place
+#[cfg(target_os = "linux")]
+const SYNCHRONOUS_HINT: &str = "x-canonical-private-synchronous";
+
+/// Action key that turns an action into an inline reply field
+#[cfg(target_os = "linux")]
+const INLINE_REPLY_ACTION_KEY: &str = "inline-reply";
+
+/// Hint carrying the placeholder text of the inline reply field
+#[cfg(target_os = "linux")]
+const REPLY_PLACEHOLDER_HINT: &str = "x-kde-reply-placeholder-text";
+
+/// `NotificationClosed` reason code for "dismissed by the user"
+#[cfg(target_os = "linux")]
+const CLOSE_REASON_DISMISSED: u32 = 2;
+
+/// Server notification ids of the notifications we've shown
+#[cfg(target_os = "linux")]
+static SERVER_IDS: OnceLock<ServerIds> = OnceLock::new();
+
+/// Last server id shown for each thread, reused as `replaces_id`
+#[cfg(target_os = "linux")]
+static THREAD_SERVER_IDS: OnceLock<ThreadServerIds> = OnceLock::new();
+
+/// A signal emitted by the notification server
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, PartialEq)]
+enum ServerSignal {
+    /// An action (or the body, via the "default" action) was invoked
+    ActionInvoked { server_id: u32, action_key: String },
+    /// Text was sent from the inline reply field
+    NotificationReplied { server_id: u32, text: String },
+    /// The notification was closed, with a reason code from the spec
+    NotificationClosed { server_id: u32, reason: u32 },
+}
+
+/// Maps the ids assigned by the notification server back to our
+/// `cushion-<uuid>` ids
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct ServerIds(Mutex<HashMap<u32, String>>);
+
+#[cfg(target_os = "linux")]
+impl ServerIds {
+    /// Remember which of our notifications the server id belongs to
+    fn insert(&self, server_id: u32, id: String) {
+        self.0.lock().unwrap().insert(server_id, id);
+    }
+
+    /// Forget one of our notifications, returning its server id
+    fn remove_notification(&self, id: &str) -> Option<u32> {
+        let mut ids = self.0.lock().unwrap();
+        let server_id = ids
+            .iter()
+            .find(|(_, notification_id)| notification_id.as_str() == id)
+            .map(|(server_id, _)| *server_id)?;
+        ids.remove(&server_id);
+        Some(server_id)
+    }
+
+    /// Forget all of our notifications, returning their server ids
+    fn remove_all(&self) -> Vec<u32> {
+        self.0.lock().unwrap().drain().map(|(server_id, _)| server_id).collect()
+    }
+
+    /// Our ids of the notifications the server hasn't closed yet
+    fn notification_ids(&self) -> Vec<String> {
+        self.0.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Resolve a server signal into our notification id and click action
+    ///
+    /// The mapping is dropped once a signal is resolved, so the
+    /// `NotificationClosed` that follows an invoked action is ignored.
+    /// Signals for notifications we didn't show are ignored too.
+    fn resolve(&self, signal: &ServerSignal) -> Option<(String, ClickAction)> {
+        let mut ids = self.0.lock().unwrap();
+        match signal {
+            ServerSignal::ActionInvoked { server_id, action_key } => {
+                let id = ids.remove(server_id)?;
+                let action = if action_key == DEFAULT_ACTION_ID {
+                    ClickAction::Body
+                } else {
+                    ClickAction::Button(action_key.clone())
+                };
+                Some((id, action))
+            }
+            ServerSignal::NotificationReplied { server_id, text } => {
+                let id = ids.remove(server_id)?;
+                Some((id, ClickAction::Reply(text.clone())))
+            }
+            ServerSignal::NotificationClosed { server_id, reason } => {
+                let id = ids.remove(server_id)?;
+                if *reason == CLOSE_REASON_DISMISSED {
+                    Some((id, ClickAction::Dismiss))
+                } else {
+                    // Expired or closed programmatically - nothing to report
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// The server id of the notification currently shown for each thread
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct ThreadServerIds(Mutex<HashMap<String, u32>>);
+
+#[cfg(target_os = "linux")]
+impl ThreadServerIds {
+    fn get(&self, thread_id: &str) -> Option<u32> {
+        self.0.lock().unwrap().get(thread_id).copied()
+    }
+
+    fn insert(&self, thread_id: String, server_id: u32) {
+        self.0.lock().unwrap().insert(thread_id, server_id);
+    }
+
+    /// Forget a thread, returning the server id of its notification
+    fn remove_thread(&self, thread_id: &str) -> Option<u32> {
+        self.0.lock().unwrap().remove(thread_id)
+    }
+
+    /// Forget the thread whose notification the server closed
+    fn closed(&self, server_id: u32) {
+        self.0.lock().unwrap().retain(|_, shown| *shown != server_id);
+    }
+
+    fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+/// Get the global server id map
+#[cfg(target_os = "linux")]
+fn server_ids() -> &'static ServerIds {
+    SERVER_IDS.get_or_init(ServerIds::default)
+}
+
+/// Get the thread -> server id map
+#[cfg(target_os = "linux")]
+fn thread_server_ids() -> &'static ThreadServerIds {
+    THREAD_SERVER_IDS.get_or_init(ThreadServerIds::default)
+}
+
+/// Ask the notification server to close a notification
+#[cfg(target_os = "linux")]
+fn close_notification(server_id: u32) -> Result<(), String> {
+    let conn = session_bus().map_err(|e| format!("Failed to connect to DBus session bus: {}", e))?;
+    conn.call_method(
+        Some(NOTIFICATIONS_INTERFACE),
+        NOTIFICATIONS_PATH,
+        Some(NOTIFICATIONS_INTERFACE),
+        "CloseNotification",
+        &(server_id,),
+    )
+    .map_err(|e| format!("Failed to close Linux notification: {}", e))?;
+    Ok(())
+}
+
+/// Ask the notification server which optional features it supports
+#[cfg(target_os = "linux")]
+fn server_capabilities(conn: &zbus::blocking::Connection) -> zbus::Result<Vec<String>> {
+    conn.call_method(
+        Some(NOTIFICATIONS_INTERFACE),
+        NOTIFICATIONS_PATH,
+        Some(NOTIFICATIONS_INTERFACE),
+        "GetCapabilities",
+        &(),
+    )?
+    .body()
+    .deserialize()
+}
+
+/// Ask the notification server for its name and version
+#[cfg(target_os = "linux")]
+fn server_information(conn: &zbus::blocking::Connection) -> zbus::Result<ServerInformation> {
+    let (name, vendor, version, spec_version): (String, String, String, String) = conn
+        .call_method(
+            Some(NOTIFICATIONS_INTERFACE),
+            NOTIFICATIONS_PATH,
+            Some(NOTIFICATIONS_INTERFACE),
+            "GetServerInformation",
+            &(),
+        )?
+        .body()
+        .deserialize()?;
+
+    Ok(ServerInformation {
+        name,
+        vendor,
+        version,
+        spec_version,
+    })
+}
+
+/// Probe the notification server on `conn` for what it supports
+#[cfg(target_os = "linux")]
+fn probe_server(conn: &zbus::blocking::Connection) -> zbus::Result<NotificationCapabilities> {
+    let server = server_information(conn)?;
+    let reported = server_capabilities(conn)?;
+    Ok(NotificationCapabilities::from_server(server, reported))
+}
+
+/// Probe the notification server on the session bus for what it supports
+#[cfg(target_os = "linux")]
+pub fn probe_capabilities() -> Result<NotificationCapabilities, String> {
+    let capabilities = session_bus()
+        .and_then(|conn| probe_server(&conn))
+        .map_err(|e| format!("Failed to query notification server capabilities: {}", e))?;
+
+    if let Some(ref server) = capabilities.server {
+        println!(
+            "🐧 Notification server: {} {} (spec {}), capabilities: {:?}",
+            server.name, server.version, server.spec_version, capabilities.reported
+        );
+    }
+    Ok(capabilities)
+}
+
+/// Parse a DBus message into a notification server signal
+#[cfg(target_os = "linux")]
+fn parse_signal(msg: &zbus::Message) -> Option<ServerSignal> {
+    let header = msg.header();
+    if header.interface()?.as_str() != NOTIFICATIONS_INTERFACE {
+        return None;
+    }
+
+    match header.member()?.as_str() {
+        "ActionInvoked" => {
+            let (server_id, action_key): (u32, String) = msg.body().deserialize().ok()?;
+            Some(ServerSignal::ActionInvoked { server_id, action_key })
+        }
+        "NotificationReplied" => {
+            let (server_id, text): (u32, String) = msg.body().deserialize().ok()?;
+            Some(ServerSignal::NotificationReplied { server_id, text })
+        }
+        "NotificationClosed" => {
+            let (server_id, reason): (u32, u32) = msg.body().deserialize().ok()?;
+            Some(ServerSignal::NotificationClosed { server_id, reason })
+        }
+        _ => None,
+    }
+}
+
+/// Listen for notification server signals on `conn`, blocking until the
+/// connection is closed
+#[cfg(target_os = "linux")]
+fn listen_for_signals<F>(conn: &zbus::blocking::Connection, mut on_signal: F) -> zbus::Result<()>
+where
+    F: FnMut(ServerSignal),
+{
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface(NOTIFICATIONS_INTERFACE)?
+        .build();
+
+    for msg in zbus::blocking::MessageIterator::for_match_rule(rule, conn, None)? {
+        if let Some(signal) = parse_signal(&msg?) {
+            on_signal(signal);
+        }
+    }
+
+    Ok(())
+}
 
 /// Setup Linux notification handling
 #[cfg(target_os = "linux")]
-pub fn setup(_manager: Arc<NotificationManager>) {
+pub fn setup(manager: Arc<NotificationManager>) {
+    std::thread::spawn(move || {
+        let conn = match session_bus() {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("❌ Failed to connect to DBus session bus: {}", e);
+                return;
+            }
+        };
+
+        println!("🐧 Listening for notification actions on DBus");
+
+        let result = listen_for_signals(&conn, |signal| {
+            if let ServerSignal::NotificationClosed { server_id, .. } = &signal {
+                thread_server_ids().closed(*server_id);
+            }
+            if let Some((id, action)) = server_ids().resolve(&signal) {
+                let url = manager.get_url(&id);
+                manager.handle_click(NotificationClick { id, url, action });
+            }
+        });
+
+        if let Err(e) = result {
+            eprintln!("❌ Notification DBus listener stopped: {}", e);
+        }
+    });
+
     println!("🐧 Linux notification system initialized");
-    // notify-rust handles action callbacks per-notification
 }
 
 /// Show a notification on Linux
+///
+/// Named sounds are played from `resource_dir`, where they're bundled.
 #[cfg(target_os = "linux")]
 pub fn show_notification(
-    id: String,
-    title: String,
-    body: String,
-    url: Option<String>,
+    request: &NotificationRequest,
+    capabilities: &NotificationCapabilities,
+    resource_dir: Option<&Path>,
 ) -> Result<(), String> {
-    let manager = NotificationManager::get()
-        .ok_or_else(|| "Notification manager not initialized".to_string())?;
+    let format = if capabilities.body_markup {
+        BodyFormat::Markup
+    } else {
+        BodyFormat::PlainText
+    };
+    let body = render_body(&request.body, format, MAX_BODY_LEN);
 
-    let notification_id = id.clone();
-    let notification_url = url.clone();
-
-    // Create notification with default action
-    let mut notification = Notification::new()
-        .summary(&title)
+    let mut notification = Notification::new();
+    notification
+        .summary(&request.title)
         .body(&body)
         .timeout(Timeout::Default)
-        .action("default", "Open")
-        .finalize();
-
-    // Set up action callback
-    let manager_clone = manager.clone();
-    notification.show().map_err(|e| format!("Failed to show Linux notification: {}", e))?;
-
-    // Note: notify-rust doesn't provide a built-in callback mechanism for actions
-    // on most Linux desktop environments. The notification server handles actions
-    // but doesn't provide a standard way to get callbacks back to the application.
-    //
-    // For full click handling on Linux, you would need to:
-    // 1. Use DBus directly to listen for action invoked signals
-    // 2. Or use a notification server that supports action callbacks
-    //
-    // For now, we'll rely on the user opening the app manually.
-    // A future enhancement could add DBus action listeners.
-
-    println!("🐧 Linux notification shown (action callbacks not yet implemented)");
+        .urgency(urgency(request.priority));
+
+    match request.sound {
+        Some(NotificationSound::None) => {
+            notification.hint(Hint::SuppressSound(true));
+        }
+        Some(NotificationSound::Named(ref name)) => {
+            if let Some(dir) = resource_dir {
+                let path = dir.join(bundled_file(name));
+                notification.hint(Hint::SoundFile(path.to_string_lossy().into_owned()));
+            }
+        }
+        Some(NotificationSound::Default) | None => {}
+    }
+
+    // Sent as the `image-path` hint
+    if let Some(ref image) = request.image {
+        if capabilities.images {
+            notification.image_path(&image.path.to_string_lossy());
+        }
+    }
+
+    // Body clicks and action buttons are reported back through
+    // `ActionInvoked` with their id, on servers that support actions at all
+    if capabilities.actions {
+        notification.action(DEFAULT_ACTION_ID, "Open");
+        for action in &request.actions {
+            notification.action(&action.id, &action.label);
+        }
+    }
+
+    // Replace the previous notification of the same thread instead of stacking
+    if let Some(ref thread_id) = request.thread_id {
+        let previous = thread_server_ids().get(thread_id);
+        if let Some(previous) = previous {
+            notification.id(previous);
+        }
+        notification.hint(Hint::Custom(SYNCHRONOUS_HINT.to_string(), thread_id.clone()));
+    }
+
+    // Inline reply where the server supports it, a plain action otherwise
+    if let Some(reply) = request.reply.as_ref().filter(|_| capabilities.actions) {
+        if capabilities.inline_reply {
+            notification.action(INLINE_REPLY_ACTION_KEY, &reply.label);
+            if let Some(ref placeholder) = reply.placeholder {
+                notification.hint(Hint::Custom(
+                    REPLY_PLACEHOLDER_HINT.to_string(),
+                    placeholder.clone(),
+                ));
+            }
+        } else {
+            notification.action(REPLY_ACTION_ID, &reply.label);
+        }
+    }
+
+    let handle = notification
+        .show()
+        .map_err(|e| format!("Failed to show Linux notification: {}", e))?;
+
+    // Clicks arrive as DBus signals keyed by the server's id
+    server_ids().insert(handle.id(), request.id.clone());
+    if let Some(ref thread_id) = request.thread_id {
+        thread_server_ids().insert(thread_id.clone(), handle.id());
+    }
+
+    println!("🐧 Linux notification shown (server id {})", handle.id());
+
+    Ok(())
+}
+
+/// Map a priority to the freedesktop urgency level
+///
+/// The spec only has three levels, and critical notifications stay on screen
+/// until dismissed, so high priority uses normal urgency.
+#[cfg(target_os = "linux")]
+fn urgency(priority: NotificationPriority) -> Urgency {
+    match priority {
+        NotificationPriority::Low => Urgency::Low,
+        NotificationPriority::Normal | NotificationPriority::High => Urgency::Normal,
+        NotificationPriority::Critical => Urgency::Critical,
+    }
+}
+
+/// Close the notification currently shown for a thread
+#[cfg(target_os = "linux")]
+pub fn clear_thread(thread_id: &str) -> Result<(), String> {
+    let server_id = thread_server_ids().remove_thread(thread_id);
+    match server_id {
+        Some(server_id) => close_notification(server_id),
+        None => Ok(()),
+    }
+}
+
+/// Close one of our notifications
+#[cfg(target_os = "linux")]
+pub fn dismiss_notification(id: &str) -> Result<(), String> {
+    match server_ids().remove_notification(id) {
+        Some(server_id) => {
+            thread_server_ids().closed(server_id);
+            close_notification(server_id)
+        }
+        None => Ok(()),
+    }
+}
 
+/// Close all of our notifications
+#[cfg(target_os = "linux")]
+pub fn dismiss_all_notifications() -> Result<(), String> {
+    thread_server_ids().clear();
+    for server_id in server_ids().remove_all() {
+        close_notification(server_id)?;
+    }
     Ok(())
 }
 
+/// Check whether notifications can be shown
+///
+/// Linux has no permission prompt: notifications work whenever a
+/// notification server is running on the session bus.
+#[cfg(target_os = "linux")]
+pub fn get_permission_status() -> Result<NotificationPermission, String> {
+    let capabilities = session_bus().and_then(|conn| server_capabilities(&conn));
+    Ok(permission_from_capabilities(capabilities))
+}
+
+/// Map the result of `GetCapabilities` to a permission status
+#[cfg(target_os = "linux")]
+fn permission_from_capabilities(capabilities: zbus::Result<Vec<String>>) -> NotificationPermission {
+    match capabilities {
+        Ok(capabilities) => NotificationPermission {
+            status: PermissionStatus::Granted,
+            capabilities,
+        },
+        Err(e) => {
+            eprintln!("❌ No notification server reachable on DBus: {}", e);
+            NotificationPermission::new(PermissionStatus::Denied)
+        }
+    }
+}
+
+/// Get the ids of our notifications that are still shown
+///
+/// The notification spec has no way to list delivered notifications, so this
+/// is based on the `NotificationClosed` signals we've seen.
+#[cfg(target_os = "linux")]
+pub fn get_delivered_notifications() -> Result<Vec<String>, String> {
+    Ok(server_ids().notification_ids())
+}
+
+/// Notification backend using the freedesktop notification server
+#[cfg(target_os = "linux")]
+pub struct LinuxBackend {
+    /// The app's resource directory, holding the bundled sounds
+    pub resource_dir: Option<PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl NotificationBackend for LinuxBackend {
+    fn show(&self, request: &NotificationRequest, capabilities: &NotificationCapabilities) -> Result<(), String> {
+        show_notification(request, capabilities, self.resource_dir.as_deref())
+    }
+
+    fn dismiss(&self, id: &str, _thread_id: Option<&str>) -> Result<(), String> {
+        dismiss_notification(id)
+    }
+
+    fn dismiss_all(&self) -> Result<(), String> {
+        dismiss_all_notifications()
+    }
+
+    fn clear_thread(&self, thread_id: &str, _ids: &[String]) -> Result<(), String> {
+        clear_thread(thread_id)
+    }
+
+    fn delivered(&self) -> Result<Vec<String>, String> {
+        get_delivered_notifications()
+    }
+
+    fn capabilities(&self) -> Result<NotificationCapabilities, String> {
+        probe_capabilities()
+    }
+
+    fn permission_status(&self) -> Result<NotificationPermission, String> {
+        get_permission_status()
+    }
+}
+
 #[cfg(not(target_os = "linux"))]
 pub fn setup(_manager: Arc<NotificationManager>) {}
 
 #[cfg(not(target_os = "linux"))]
 pub fn show_notification(
-    _id: String,
-    _title: String,
-    _body: String,
-    _url: Option<String>,
+    _request: &NotificationRequest,
+    _capabilities: &NotificationCapabilities,
+    _resource_dir: Option<&Path>,
 ) -> Result<(), String> {
     Err("Linux notifications not supported on this platform".to_string())
 }
+
+#[cfg(not(target_os = "linux"))]
+pub fn clear_thread(_thread_id: &str) -> Result<(), String> {
+    Err("Linux notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn dismiss_notification(_id: &str) -> Result<(), String> {
+    Err("Linux notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn dismiss_all_notifications() -> Result<(), String> {
+    Err("Linux notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn probe_capabilities() -> Result<NotificationCapabilities, String> {
+    Err("Linux notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_permission_status() -> Result<NotificationPermission, String> {
+    Err("Linux notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get_delivered_notifications() -> Result<Vec<String>, String> {
+    Err("Linux notifications not supported on this platform".to_string())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    /// Connect a fake notification server and a client over a private
+    /// peer-to-peer bus
+    fn private_bus() -> (zbus::blocking::Connection, zbus::blocking::Connection) {
+        connect(None)
+    }
+
+    /// Like `private_bus`, with the server answering queries about itself at
+    /// `path`
+    fn fake_server_bus(path: &'static str) -> (zbus::blocking::Connection, zbus::blocking::Connection) {
+        connect(Some(path))
+    }
+
+    fn connect(serve_at: Option<&'static str>) -> (zbus::blocking::Connection, zbus::blocking::Connection) {
+        let (server_stream, client_stream) = UnixStream::pair().unwrap();
+        let guid = zbus::Guid::generate();
+
+        // The object server is set up while connecting so that no method call
+        // can arrive before it's listening
+        let server = std::thread::spawn(move || {
+            let builder = zbus::blocking::connection::Builder::unix_stream(server_stream)
+                .server(guid)
+                .unwrap()
+                .p2p();
+            match serve_at {
+                Some(path) => builder.serve_at(path, FakeServer).unwrap(),
+                None => builder,
+            }
+            .build()
+            .unwrap()
+        });
+        let client = zbus::blocking::connection::Builder::unix_stream(client_stream)
+            .p2p()
+            .build()
+            .unwrap();
+
+        (server.join().unwrap(), client)
+    }
+
+    fn emit<B>(server: &zbus::blocking::Connection, member: &str, body: &B)
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        server
+            .emit_signal(
+                None::<&str>,
+                NOTIFICATIONS_PATH,
+                NOTIFICATIONS_INTERFACE,
+                member,
+                body,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_listener_receives_server_signals() {
+        let (server, client) = private_bus();
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let _ = listen_for_signals(&client, |signal| {
+                let _ = tx.send(signal);
+            });
+        });
+
+        // Give the listener time to install its match rule
+        std::thread::sleep(Duration::from_millis(100));
+
+        emit(&server, "ActionInvoked", &(7u32, "default"));
+        emit(&server, "NotificationReplied", &(8u32, "On it!"));
+        emit(&server, "NotificationClosed", &(7u32, CLOSE_REASON_DISMISSED));
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            rx.recv_timeout(timeout).unwrap(),
+            ServerSignal::ActionInvoked { server_id: 7, action_key: "default".to_string() }
+        );
+        assert_eq!(
+            rx.recv_timeout(timeout).unwrap(),
+            ServerSignal::NotificationReplied { server_id: 8, text: "On it!".to_string() }
+        );
+        assert_eq!(
+            rx.recv_timeout(timeout).unwrap(),
+            ServerSignal::NotificationClosed { server_id: 7, reason: CLOSE_REASON_DISMISSED }
+        );
+    }
+
+    /// Notification server that only answers queries about itself
+    struct FakeServer;
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl FakeServer {
+        fn get_capabilities(&self) -> Vec<String> {
+            vec!["actions".to_string(), "body".to_string()]
+        }
+
+        fn get_server_information(&self) -> (String, String, String, String) {
+            (
+                "fake-server".to_string(),
+                "Cushion".to_string(),
+                "1.0".to_string(),
+                "1.2".to_string(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_permission_reflects_server_capabilities() {
+        // Something else is served, so the call fails with an unknown object
+        // error
+        let (_server, client) = fake_server_bus("/org/example/Other");
+        let permission = permission_from_capabilities(server_capabilities(&client));
+        assert_eq!(permission.status, PermissionStatus::Denied);
+        assert!(permission.capabilities.is_empty());
+
+        let (_server, client) = fake_server_bus(NOTIFICATIONS_PATH);
+        let permission = permission_from_capabilities(server_capabilities(&client));
+        assert_eq!(permission.status, PermissionStatus::Granted);
+        assert_eq!(permission.capabilities, vec!["actions", "body"]);
+    }
+
+    #[test]
+    fn test_probe_reads_server_information_and_capabilities() {
+        let (_server, client) = fake_server_bus(NOTIFICATIONS_PATH);
+
+        let capabilities = probe_server(&client).unwrap();
+        assert_eq!(capabilities.server.unwrap().name, "fake-server");
+        assert!(capabilities.actions);
+        assert!(!capabilities.body_markup);
+        assert!(!capabilities.images);
+    }
+
+    #[test]
+    fn test_resolve_maps_server_ids_to_cushion_ids() {
+        let ids = ServerIds::default();
+        ids.insert(1, "cushion-body".to_string());
+        ids.insert(2, "cushion-button".to_string());
+        ids.insert(3, "cushion-dismiss".to_string());
+        ids.insert(4, "cushion-reply".to_string());
+
+        let body = ids.resolve(&ServerSignal::ActionInvoked {
+            server_id: 1,
+            action_key: "default".to_string(),
+        });
+        assert!(matches!(body, Some((ref id, ClickAction::Body)) if id == "cushion-body"));
+
+        let button = ids.resolve(&ServerSignal::ActionInvoked {
+            server_id: 2,
+            action_key: "mark-read".to_string(),
+        });
+        assert!(matches!(
+            button,
+            Some((ref id, ClickAction::Button(ref key))) if id == "cushion-button" && key == "mark-read"
+        ));
+
+        let dismiss = ids.resolve(&ServerSignal::NotificationClosed {
+            server_id: 3,
+            reason: CLOSE_REASON_DISMISSED,
+        });
+        assert!(matches!(dismiss, Some((ref id, ClickAction::Dismiss)) if id == "cushion-dismiss"));
+
+        let reply = ids.resolve(&ServerSignal::NotificationReplied {
+            server_id: 4,
+            text: "Sounds good".to_string(),
+        });
+        assert!(matches!(
+            reply,
+            Some((ref id, ClickAction::Reply(ref text))) if id == "cushion-reply" && text == "Sounds good"
+        ));
+    }
+
+    #[test]
+    fn test_resolve_ignores_close_after_action_and_unknown_ids() {
+        let ids = ServerIds::default();
+        ids.insert(1, "cushion-a".to_string());
+        ids.insert(2, "cushion-b".to_string());
+
+        assert!(ids
+            .resolve(&ServerSignal::ActionInvoked { server_id: 1, action_key: "default".to_string() })
+            .is_some());
+        // The server closes the notification after the action was invoked
+        assert!(ids
+            .resolve(&ServerSignal::NotificationClosed { server_id: 1, reason: CLOSE_REASON_DISMISSED })
+            .is_none());
+
+        // Expired notifications are not reported as dismissals
+        assert!(ids
+            .resolve(&ServerSignal::NotificationClosed { server_id: 2, reason: 1 })
+            .is_none());
+
+        // Notifications from other applications are ignored
+        assert!(ids
+            .resolve(&ServerSignal::ActionInvoked { server_id: 99, action_key: "default".to_string() })
+            .is_none());
+    }
+
+    #[test]
+    fn test_closed_notifications_leave_the_thread_map() {
+        let threads = ThreadServerIds::default();
+        threads.insert("thread-a".to_string(), 1);
+        threads.insert("thread-b".to_string(), 2);
+        threads.insert("thread-c".to_string(), 3);
+
+        threads.closed(1);
+        assert_eq!(threads.get("thread-a"), None);
+        assert_eq!(threads.get("thread-b"), Some(2));
+
+        assert_eq!(threads.remove_thread("thread-b"), Some(2));
+        assert_eq!(threads.remove_thread("thread-b"), None);
+
+        // Closing something we don't track changes nothing
+        threads.closed(99);
+        assert_eq!(threads.get("thread-c"), Some(3));
+        threads.clear();
+        assert_eq!(threads.get("thread-c"), None);
+    }
+
+    #[test]
+    fn test_remove_notification_by_cushion_id() {
+        let ids = ServerIds::default();
+        ids.insert(1, "cushion-a".to_string());
+        ids.insert(2, "cushion-b".to_string());
+
+        assert_eq!(ids.remove_notification("cushion-b"), Some(2));
+        assert_eq!(ids.remove_notification("cushion-b"), None);
+        assert_eq!(ids.notification_ids(), vec!["cushion-a".to_string()]);
+
+        assert_eq!(ids.remove_all(), vec![1]);
+        assert!(ids.notification_ids().is_empty());
+    }
+}
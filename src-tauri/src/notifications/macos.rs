@@ -3,13 +3,25 @@
 /// This provides native macOS notifications with click handling via
 /// UNUserNotificationCenterDelegate.
 
-use super::{ClickAction, NotificationClick, NotificationManager};
+use super::{
+    ClickAction, NotificationClick, NotificationManager, NotificationPermission,
+    NotificationRequest,
+};
 use std::sync::Arc;
 
+#[cfg(target_os = "macos")]
+use super::{
+    backend::NotificationBackend,
+    categories::{CategoryActions, CategoryRegistry},
+    markup::{render_body, BodyFormat},
+    sounds::bundled_file,
+    NotificationCapabilities, NotificationPriority, NotificationReply, NotificationSound,
+    PermissionStatus, REPLY_ACTION_ID,
+};
 #[cfg(target_os = "macos")]
 use cocoa::base::{id, nil, BOOL, YES};
 #[cfg(target_os = "macos")]
-use cocoa::foundation::{NSString, NSAutoreleasePool, NSInteger, NSUInteger};
+use cocoa::foundation::{NSArray, NSString, NSAutoreleasePool, NSInteger, NSUInteger};
 #[cfg(target_os = "macos")]
 use objc::declare::ClassDecl;
 #[cfg(target_os = "macos")]
@@ -20,6 +32,13 @@ use objc::{class, msg_send, sel, sel_impl};
 use block::ConcreteBlock;
 #[cfg(target_os = "macos")]
 use std::sync::mpsc;
+#[cfg(target_os = "macos")]
+use std::sync::Mutex;
+
+/// Longest body shown, in graphemes; Notification Center only shows a few
+/// lines even when expanded
+#[cfg(target_os = "macos")]
+const MAX_BODY_LEN: usize = 400;
 
 // UNAuthorizationOptions
 #[cfg(target_os = "macos")]
@@ -29,6 +48,22 @@ const UN_AUTHORIZATION_OPTION_SOUND: NSUInteger = 1 << 1;
 #[cfg(target_os = "macos")]
 const UN_AUTHORIZATION_OPTION_ALERT: NSUInteger = 1 << 2;
 
+// UNNotificationActionOptions
+#[cfg(target_os = "macos")]
+const UN_NOTIFICATION_ACTION_OPTION_NONE: NSUInteger = 0;
+
+// UNNotificationCategoryOptions
+#[cfg(target_os = "macos")]
+const UN_NOTIFICATION_CATEGORY_OPTION_CUSTOM_DISMISS_ACTION: NSUInteger = 1 << 0;
+
+// UNNotificationInterruptionLevel
+#[cfg(target_os = "macos")]
+const UN_NOTIFICATION_INTERRUPTION_LEVEL_PASSIVE: NSUInteger = 0;
+#[cfg(target_os = "macos")]
+const UN_NOTIFICATION_INTERRUPTION_LEVEL_ACTIVE: NSUInteger = 1;
+#[cfg(target_os = "macos")]
+const UN_NOTIFICATION_INTERRUPTION_LEVEL_TIME_SENSITIVE: NSUInteger = 2;
+
 // UNAuthorizationStatus
 #[cfg(target_os = "macos")]
 const UN_AUTHORIZATION_STATUS_NOT_DETERMINED: NSInteger = 0;
@@ -36,10 +71,18 @@ const UN_AUTHORIZATION_STATUS_NOT_DETERMINED: NSInteger = 0;
 const UN_AUTHORIZATION_STATUS_DENIED: NSInteger = 1;
 #[cfg(target_os = "macos")]
 const UN_AUTHORIZATION_STATUS_AUTHORIZED: NSInteger = 2;
+#[cfg(target_os = "macos")]
+const UN_AUTHORIZATION_STATUS_PROVISIONAL: NSInteger = 3;
+#[cfg(target_os = "macos")]
+const UN_AUTHORIZATION_STATUS_EPHEMERAL: NSInteger = 4;
 
 #[cfg(target_os = "macos")]
 static mut NOTIFICATION_MANAGER: Option<Arc<NotificationManager>> = None;
 
+/// Button and reply sets registered as notification categories
+#[cfg(target_os = "macos")]
+static CATEGORIES: Mutex<CategoryRegistry> = Mutex::new(CategoryRegistry::new());
+
 /// Check if we're running in a proper app bundle (required for notifications)
 #[cfg(target_os = "macos")]
 fn is_bundled_app() -> bool {
@@ -79,13 +122,12 @@ pub fn setup(manager: Arc<NotificationManager>) {
 
 /// Request notification permission from macOS
 /// This will show the system permission dialog if permission hasn't been determined yet.
-/// Returns "granted" or "denied".
 #[cfg(target_os = "macos")]
-pub fn request_notification_permission() -> Result<String, String> {
+pub fn request_notification_permission() -> Result<NotificationPermission, String> {
     // Skip in dev mode (no bundle)
     if !is_bundled_app() {
         println!("⚠️ Skipping notification permission request - not running in app bundle (dev mode)");
-        return Ok("denied".to_string());
+        return Ok(NotificationPermission::new(PermissionStatus::Denied));
     }
 
     unsafe {
@@ -125,14 +167,14 @@ pub fn request_notification_permission() -> Result<String, String> {
                     println!("❌ Permission request error: {}", err);
                     return Err(err);
                 }
-                let status = if granted { "granted" } else { "denied" };
-                println!("🔔 Notification permission: {}", status);
-                Ok(status.to_string())
+                let status = if granted { PermissionStatus::Granted } else { PermissionStatus::Denied };
+                println!("🔔 Notification permission: {:?}", status);
+                Ok(NotificationPermission::new(status))
             }
             Err(mpsc::RecvTimeoutError::Timeout) => {
                 println!("⚠️ Permission request timed out - checking current status");
                 // Fall back to checking current authorization status
-                get_current_authorization_status()
+                get_permission_status()
             }
             Err(e) => {
                 println!("❌ Failed to receive permission result: {}", e);
@@ -144,10 +186,10 @@ pub fn request_notification_permission() -> Result<String, String> {
 
 /// Get current notification authorization status without prompting
 #[cfg(target_os = "macos")]
-fn get_current_authorization_status() -> Result<String, String> {
+pub fn get_permission_status() -> Result<NotificationPermission, String> {
     // Skip in dev mode (no bundle)
     if !is_bundled_app() {
-        return Ok("denied".to_string());
+        return Ok(NotificationPermission::new(PermissionStatus::Denied));
     }
 
     unsafe {
@@ -168,25 +210,20 @@ fn get_current_authorization_status() -> Result<String, String> {
 
         match rx.recv_timeout(std::time::Duration::from_secs(5)) {
             Ok(status) => {
-                let status_str = match status {
-                    UN_AUTHORIZATION_STATUS_NOT_DETERMINED => {
-                        println!("🔔 Authorization status: not_determined (permission dialog didn't show)");
-                        "denied" // Treat as denied if we couldn't prompt
-                    }
-                    UN_AUTHORIZATION_STATUS_DENIED => {
-                        println!("🔔 Authorization status: denied");
-                        "denied"
-                    }
-                    UN_AUTHORIZATION_STATUS_AUTHORIZED => {
-                        println!("🔔 Authorization status: granted");
-                        "granted"
+                let status = match status {
+                    UN_AUTHORIZATION_STATUS_NOT_DETERMINED => PermissionStatus::NotDetermined,
+                    UN_AUTHORIZATION_STATUS_DENIED => PermissionStatus::Denied,
+                    UN_AUTHORIZATION_STATUS_AUTHORIZED | UN_AUTHORIZATION_STATUS_EPHEMERAL => {
+                        PermissionStatus::Granted
                     }
+                    UN_AUTHORIZATION_STATUS_PROVISIONAL => PermissionStatus::Provisional,
                     _ => {
                         println!("🔔 Authorization status: unknown ({})", status);
-                        "denied"
+                        PermissionStatus::Denied
                     }
                 };
-                Ok(status_str.to_string())
+                println!("🔔 Authorization status: {:?}", status);
+                Ok(NotificationPermission::new(status))
             }
             Err(_) => {
                 println!("❌ Failed to get notification settings (timed out)");
@@ -197,9 +234,13 @@ fn get_current_authorization_status() -> Result<String, String> {
 }
 
 #[cfg(not(target_os = "macos"))]
-pub fn request_notification_permission() -> Result<String, String> {
-    // On non-macOS platforms, return granted (Linux doesn't require permission)
-    Ok("granted".to_string())
+pub fn request_notification_permission() -> Result<NotificationPermission, String> {
+    Err("macOS notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn get_permission_status() -> Result<NotificationPermission, String> {
+    Err("macOS notifications not supported on this platform".to_string())
 }
 
 /// Create the UNUserNotificationCenterDelegate
@@ -230,8 +271,17 @@ unsafe fn create_notification_delegate() -> id {
 
             println!("🍎 Notification response - ID: {}, Action: {}", identifier, action_identifier);
 
+            // Text input responses carry the typed reply
+            let is_text_response: BOOL = msg_send![
+                response,
+                isKindOfClass: class!(UNTextInputNotificationResponse)
+            ];
+
             // Determine the action type
-            let action = if action_identifier == "com.apple.UNNotificationDefaultActionIdentifier" {
+            let action = if action_identifier == REPLY_ACTION_ID && is_text_response == YES {
+                let text_ns: id = msg_send![response, userText];
+                ClickAction::Reply(nsstring_to_string(text_ns))
+            } else if action_identifier == "com.apple.UNNotificationDefaultActionIdentifier" {
                 ClickAction::Body
             } else if action_identifier == "com.apple.UNNotificationDismissActionIdentifier" {
                 ClickAction::Dismiss
@@ -241,7 +291,7 @@ unsafe fn create_notification_delegate() -> id {
 
             // Get the URL from metadata if stored
             let url = if let Some(ref manager) = NOTIFICATION_MANAGER {
-                manager.get_metadata(&identifier)
+                manager.get_url(&identifier)
             } else {
                 None
             };
@@ -278,14 +328,84 @@ unsafe fn create_notification_delegate() -> id {
     delegate
 }
 
+/// Create a UNTextInputNotificationAction for inline replies
+#[cfg(target_os = "macos")]
+unsafe fn create_reply_action(reply: &NotificationReply) -> id {
+    let action_id_ns = NSString::alloc(nil).init_str(REPLY_ACTION_ID);
+    let label_ns = NSString::alloc(nil).init_str(&reply.label);
+    let button_ns = NSString::alloc(nil).init_str("Send");
+    let placeholder_ns = NSString::alloc(nil).init_str(reply.placeholder.as_deref().unwrap_or_default());
+    msg_send![
+        class!(UNTextInputNotificationAction),
+        actionWithIdentifier: action_id_ns
+        title: label_ns
+        options: UN_NOTIFICATION_ACTION_OPTION_NONE
+        textInputButtonTitle: button_ns
+        textInputPlaceholder: placeholder_ns
+    ]
+}
+
+/// Create a UNNotificationCategory holding the given action buttons
+#[cfg(target_os = "macos")]
+unsafe fn create_category(identifier: &str, category: &CategoryActions) -> id {
+    let mut action_objects: Vec<id> = Vec::new();
+    if let Some(ref reply) = category.reply {
+        action_objects.push(create_reply_action(reply));
+    }
+    for action in &category.actions {
+        let action_id_ns = NSString::alloc(nil).init_str(&action.id);
+        let label_ns = NSString::alloc(nil).init_str(&action.label);
+        let action_object: id = msg_send![
+            class!(UNNotificationAction),
+            actionWithIdentifier: action_id_ns
+            title: label_ns
+            options: UN_NOTIFICATION_ACTION_OPTION_NONE
+        ];
+        action_objects.push(action_object);
+    }
+
+    let identifier_ns = NSString::alloc(nil).init_str(identifier);
+    let actions_array = NSArray::arrayWithObjects(nil, &action_objects);
+    let intents_array = NSArray::array(nil);
+
+    // Custom dismiss action so dismissals reach the delegate as well
+    msg_send![
+        class!(UNNotificationCategory),
+        categoryWithIdentifier: identifier_ns
+        actions: actions_array
+        intentIdentifiers: intents_array
+        options: UN_NOTIFICATION_CATEGORY_OPTION_CUSTOM_DISMISS_ACTION
+    ]
+}
+
+/// Register a category for the action buttons and return its identifier
+///
+/// `setNotificationCategories:` replaces every registered category, so the
+/// whole set is registered again whenever a new combination of buttons is
+/// used; the registry keeps that set bounded (see the `categories` module).
+#[cfg(target_os = "macos")]
+unsafe fn register_category(center: id, category: CategoryActions) -> String {
+    let mut registry = CATEGORIES.lock().unwrap();
+    let (identifier, changed) = registry.use_category(category);
+    if !changed {
+        return identifier;
+    }
+
+    let category_objects: Vec<id> = registry
+        .categories()
+        .map(|(category_id, category_actions)| create_category(category_id, category_actions))
+        .collect();
+    let categories_array = NSArray::arrayWithObjects(nil, &category_objects);
+    let categories_set: id = msg_send![class!(NSSet), setWithArray: categories_array];
+    let _: () = msg_send![center, setNotificationCategories: categories_set];
+
+    println!("🍎 Registered notification category: {}", identifier);
+    identifier
+}
+
 /// Show a notification on macOS
 #[cfg(target_os = "macos")]
-pub fn show_notification(
-    id: String,
-    title: String,
-    body: String,
-    _url: Option<String>,
-) -> Result<(), String> {
+pub fn show_notification(request: &NotificationRequest) -> Result<(), String> {
     // Skip in dev mode (no bundle)
     if !is_bundled_app() {
         println!("⚠️ Skipping notification - not running in app bundle (dev mode)");
@@ -304,17 +424,67 @@ pub fn show_notification(
         let content: id = msg_send![content_class, new];
 
         // Set title
-        let title_ns = NSString::alloc(nil).init_str(&title);
+        let title_ns = NSString::alloc(nil).init_str(&request.title);
         let _: () = msg_send![content, setTitle: title_ns];
 
-        // Set body
+        // Set body, with markdown stripped
+        let body = render_body(&request.body, BodyFormat::PlainText, MAX_BODY_LEN);
         let body_ns = NSString::alloc(nil).init_str(&body);
         let _: () = msg_send![content, setBody: body_ns];
 
+        // Group notifications of the same thread together
+        if let Some(ref thread_id) = request.thread_id {
+            let thread_ns = NSString::alloc(nil).init_str(thread_id);
+            let _: () = msg_send![content, setThreadIdentifier: thread_ns];
+        }
+
+        // Interruption levels are available from macOS 12
+        let responds: BOOL = msg_send![content, respondsToSelector: sel!(setInterruptionLevel:)];
+        if responds == YES {
+            let level = interruption_level(request.priority);
+            let _: () = msg_send![content, setInterruptionLevel: level];
+        }
+
+        let sound: id = match request.sound {
+            Some(NotificationSound::None) => nil,
+            Some(NotificationSound::Named(ref name)) => {
+                // Found by name in the app bundle's resources
+                let name_ns = NSString::alloc(nil).init_str(&bundled_file(name));
+                msg_send![class!(UNNotificationSound), soundNamed: name_ns]
+            }
+            Some(NotificationSound::Default) | None => {
+                msg_send![class!(UNNotificationSound), defaultSound]
+            }
+        };
+        if sound != nil {
+            let _: () = msg_send![content, setSound: sound];
+        }
+
+        // Avatars and attachment thumbnails are both shown as an attachment
+        if let Some(ref image) = request.image {
+            if let Some(attachment) = create_attachment(&image.path) {
+                let attachments = NSArray::arrayWithObject(nil, attachment);
+                let _: () = msg_send![content, setAttachments: attachments];
+            }
+        }
+
+        // Attach action buttons and the reply field through a notification category
+        if !request.actions.is_empty() || request.reply.is_some() {
+            let category_id = register_category(
+                center,
+                CategoryActions {
+                    actions: request.actions.clone(),
+                    reply: request.reply.clone(),
+                },
+            );
+            let category_ns = NSString::alloc(nil).init_str(&category_id);
+            let _: () = msg_send![content, setCategoryIdentifier: category_ns];
+        }
+
         // Create notification request
         let request_class = class!(UNNotificationRequest);
-        let id_ns = NSString::alloc(nil).init_str(&id);
-        let request: id = msg_send![
+        let id_ns = NSString::alloc(nil).init_str(&request.id);
+        let notification_request: id = msg_send![
             request_class,
             requestWithIdentifier: id_ns
             content: content
@@ -333,12 +503,138 @@ pub fn show_notification(
         });
         let block = block.copy();
 
-        let _: () = msg_send![center, addNotificationRequest: request withCompletionHandler: &*block];
+        let _: () = msg_send![center, addNotificationRequest: notification_request withCompletionHandler: &*block];
 
         Ok(())
     }
 }
 
+/// Map a priority to a UNNotificationInterruptionLevel
+///
+/// The critical level needs a special entitlement, so critical notifications
+/// are sent as time sensitive. That level needs the
+/// `com.apple.developer.usernotifications.time-sensitive` entitlement too,
+/// which the app isn't signed with, so macOS delivers both as active.
+#[cfg(target_os = "macos")]
+fn interruption_level(priority: NotificationPriority) -> NSUInteger {
+    match priority {
+        NotificationPriority::Low => UN_NOTIFICATION_INTERRUPTION_LEVEL_PASSIVE,
+        NotificationPriority::Normal => UN_NOTIFICATION_INTERRUPTION_LEVEL_ACTIVE,
+        NotificationPriority::High | NotificationPriority::Critical => {
+            UN_NOTIFICATION_INTERRUPTION_LEVEL_TIME_SENSITIVE
+        }
+    }
+}
+
+/// Create a notification attachment for an image file
+///
+/// The file is moved into the notification store, so it must be our own copy.
+#[cfg(target_os = "macos")]
+unsafe fn create_attachment(path: &std::path::Path) -> Option<id> {
+    let path_ns = NSString::alloc(nil).init_str(&path.to_string_lossy());
+    let url: id = msg_send![class!(NSURL), fileURLWithPath: path_ns];
+    let identifier = NSString::alloc(nil).init_str("image");
+
+    let mut error: id = nil;
+    let attachment: id = msg_send![
+        class!(UNNotificationAttachment),
+        attachmentWithIdentifier: identifier
+        URL: url
+        options: nil
+        error: &mut error
+    ];
+
+    if attachment == nil {
+        let error_desc: id = msg_send![error, localizedDescription];
+        println!("❌ Failed to attach notification image: {}", nsstring_to_string(error_desc));
+        return None;
+    }
+    Some(attachment)
+}
+
+/// Remove delivered notifications from Notification Center
+#[cfg(target_os = "macos")]
+pub fn remove_delivered_notifications(ids: &[String]) -> Result<(), String> {
+    // Skip in dev mode (no bundle)
+    if !is_bundled_app() || ids.is_empty() {
+        return Ok(());
+    }
+
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+
+        let center_class = class!(UNUserNotificationCenter);
+        let center: id = msg_send![center_class, currentNotificationCenter];
+
+        let id_objects: Vec<id> = ids
+            .iter()
+            .map(|notification_id| NSString::alloc(nil).init_str(notification_id))
+            .collect();
+        let ids_array = NSArray::arrayWithObjects(nil, &id_objects);
+        let _: () = msg_send![center, removeDeliveredNotificationsWithIdentifiers: ids_array];
+
+        println!("🍎 Removed {} delivered notification(s)", ids.len());
+        Ok(())
+    }
+}
+
+/// Remove all of our delivered notifications from Notification Center
+#[cfg(target_os = "macos")]
+pub fn remove_all_delivered_notifications() -> Result<(), String> {
+    // Skip in dev mode (no bundle)
+    if !is_bundled_app() {
+        return Ok(());
+    }
+
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+
+        let center_class = class!(UNUserNotificationCenter);
+        let center: id = msg_send![center_class, currentNotificationCenter];
+        let _: () = msg_send![center, removeAllDeliveredNotifications];
+
+        println!("🍎 Removed all delivered notifications");
+        Ok(())
+    }
+}
+
+/// Get the identifiers of the notifications still shown in Notification Center
+#[cfg(target_os = "macos")]
+pub fn get_delivered_notifications() -> Result<Vec<String>, String> {
+    // Skip in dev mode (no bundle)
+    if !is_bundled_app() {
+        return Ok(Vec::new());
+    }
+
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+
+        let center_class = class!(UNUserNotificationCenter);
+        let center: id = msg_send![center_class, currentNotificationCenter];
+
+        let (tx, rx) = mpsc::channel::<Vec<String>>();
+
+        let block = ConcreteBlock::new(move |notifications: id| {
+            let count: usize = msg_send![notifications, count];
+            let ids = (0..count)
+                .map(|index| {
+                    let notification: id = msg_send![notifications, objectAtIndex: index];
+                    let request: id = msg_send![notification, request];
+                    let identifier: id = msg_send![request, identifier];
+                    nsstring_to_string(identifier)
+                })
+                .collect();
+            let _ = tx.send(ids);
+        });
+        let block = block.copy();
+
+        let _: () = msg_send![center, getDeliveredNotificationsWithCompletionHandler: &*block];
+
+        rx.recv_timeout(std::time::Duration::from_secs(5))
+            .map_err(|_| "Failed to get delivered notifications".to_string())
+    }
+}
+
 /// Helper to convert NSString to Rust String
 #[cfg(target_os = "macos")]
 unsafe fn nsstring_to_string(ns_string: id) -> String {
@@ -354,15 +650,64 @@ unsafe fn nsstring_to_string(ns_string: id) -> String {
         .into_owned()
 }
 
+/// Notification backend using UNUserNotificationCenter
+#[cfg(target_os = "macos")]
+pub struct MacosBackend;
+
+#[cfg(target_os = "macos")]
+impl NotificationBackend for MacosBackend {
+    fn show(&self, request: &NotificationRequest, _capabilities: &NotificationCapabilities) -> Result<(), String> {
+        show_notification(request)
+    }
+
+    fn dismiss(&self, id: &str, _thread_id: Option<&str>) -> Result<(), String> {
+        remove_delivered_notifications(&[id.to_string()])
+    }
+
+    fn dismiss_all(&self) -> Result<(), String> {
+        remove_all_delivered_notifications()
+    }
+
+    fn clear_thread(&self, _thread_id: &str, ids: &[String]) -> Result<(), String> {
+        remove_delivered_notifications(ids)
+    }
+
+    fn delivered(&self) -> Result<Vec<String>, String> {
+        get_delivered_notifications()
+    }
+
+    fn capabilities(&self) -> Result<NotificationCapabilities, String> {
+        Ok(NotificationCapabilities::native())
+    }
+
+    fn permission_status(&self) -> Result<NotificationPermission, String> {
+        get_permission_status()
+    }
+
+    fn request_permission(&self) -> Result<NotificationPermission, String> {
+        request_notification_permission()
+    }
+}
+
 #[cfg(not(target_os = "macos"))]
 pub fn setup(_manager: Arc<NotificationManager>) {}
 
 #[cfg(not(target_os = "macos"))]
-pub fn show_notification(
-    _id: String,
-    _title: String,
-    _body: String,
-    _url: Option<String>,
-) -> Result<(), String> {
+pub fn show_notification(_request: &NotificationRequest) -> Result<(), String> {
+    Err("macOS notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn remove_delivered_notifications(_ids: &[String]) -> Result<(), String> {
+    Err("macOS notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn remove_all_delivered_notifications() -> Result<(), String> {
+    Err("macOS notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn get_delivered_notifications() -> Result<Vec<String>, String> {
     Err("macOS notifications not supported on this platform".to_string())
 }
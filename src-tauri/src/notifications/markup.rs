@@ -0,0 +1,374 @@
+//! Notification body formatting
+//!
+//! Cushion posts are written in markdown. Notifications understand a small
+//! subset of it: `**bold**`, `*italic*`, `_italic_`, `~~strikethrough~~`,
+//! `` `code` ``, `[links](url)`, headings, quotes and list bullets. Linux
+//! servers with `body-markup` get bold and italic as `<b>` and `<i>`; every
+//! other backend gets clean plain text.
+//!
+//! Bodies are also cut to the platform's length limit, counting grapheme
+//! clusters so an emoji or accented letter is never split.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Appended to a body cut to the length limit
+const ELLIPSIS: &str = "…";
+
+/// How the backend displays the body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyFormat {
+    PlainText,
+    /// The freedesktop `body-markup` subset of HTML
+    #[cfg_attr(not(any(target_os = "linux", test)), allow(dead_code))]
+    Markup,
+}
+
+/// Inline style of a run of text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    italic: bool,
+}
+
+/// A run of text with one style
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Span {
+    text: String,
+    style: Style,
+}
+
+/// Render a markdown body for display, cut to at most `max_len` graphemes
+pub fn render_body(source: &str, format: BodyFormat, max_len: usize) -> String {
+    let mut spans = parse(source);
+    truncate(&mut spans, max_len);
+
+    match format {
+        BodyFormat::PlainText => spans.into_iter().map(|span| span.text).collect(),
+        BodyFormat::Markup => spans.iter().map(markup).collect(),
+    }
+}
+
+/// Escape text for servers that render the body as markup
+///
+/// Entities already in the text are escaped too, so `&amp;` shows as
+/// written rather than as `&`.
+pub fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn markup(span: &Span) -> String {
+    let mut text = escape_markup(&span.text);
+    if span.style.italic {
+        text = format!("<i>{}</i>", text);
+    }
+    if span.style.bold {
+        text = format!("<b>{}</b>", text);
+    }
+    text
+}
+
+/// Split markdown into styled runs of text, one line at a time
+fn parse(source: &str) -> Vec<Span> {
+    let lines: Vec<&str> = source.lines().map(str::trim_end).collect();
+    let first = lines.iter().position(|line| !line.is_empty());
+    let last = lines.iter().rposition(|line| !line.is_empty());
+    let (Some(first), Some(last)) = (first, last) else {
+        return Vec::new();
+    };
+
+    let mut spans = Vec::new();
+    for (index, line) in lines[first..=last].iter().enumerate() {
+        if index > 0 {
+            push(&mut spans, "\n", Style::default());
+        }
+
+        let (line, style) = strip_block_marker(line, &mut spans);
+        parse_inline(line, style, &mut spans);
+    }
+    spans
+}
+
+/// Strip a heading, quote or list marker from the start of a line
+///
+/// Headings are shown in bold and bullets as `•`; returns the rest of the
+/// line and the style it starts in.
+fn strip_block_marker<'a>(line: &'a str, spans: &mut Vec<Span>) -> (&'a str, Style) {
+    let indent = line.len() - line.trim_start().len();
+    let trimmed = line.trim_start();
+
+    let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+        let style = Style {
+            bold: true,
+            ..Style::default()
+        };
+        return (trimmed[hashes..].trim_start(), style);
+    }
+
+    if let Some(rest) = trimmed
+        .strip_prefix("> ")
+        .or_else(|| trimmed.strip_prefix('>'))
+    {
+        return (rest, Style::default());
+    }
+
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(rest) = trimmed.strip_prefix(bullet) {
+            push(spans, &format!("{}• ", &line[..indent]), Style::default());
+            return (rest, Style::default());
+        }
+    }
+
+    (line, Style::default())
+}
+
+/// Parse emphasis, code and links within a line
+fn parse_inline(line: &str, mut style: Style, spans: &mut Vec<Span>) {
+    let mut text = String::new();
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        let previous = text
+            .chars()
+            .last()
+            .or_else(|| spans.last().and_then(|s| s.text.chars().last()));
+
+        // Backslash escapes a markdown character
+        if c == '\\' {
+            if let Some(escaped) = rest[1..]
+                .chars()
+                .next()
+                .filter(|c| c.is_ascii_punctuation())
+            {
+                text.push(escaped);
+                rest = &rest[1 + escaped.len_utf8()..];
+                continue;
+            }
+        }
+
+        // Code is shown as written
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                text.push_str(&rest[1..1 + end]);
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+
+        // Links show their text
+        if c == '[' {
+            if let Some((label, after)) = split_link(rest) {
+                push(spans, &std::mem::take(&mut text), style);
+                parse_inline(label, style, spans);
+                rest = after;
+                continue;
+            }
+        }
+
+        // Strikethrough can't be shown, so only its delimiters are dropped
+        if let Some(end) = rest.strip_prefix("~~").and_then(|after| after.find("~~")) {
+            text.push_str(&rest[2..2 + end]);
+            rest = &rest[end + 4..];
+            continue;
+        }
+
+        if let Some(delimiter) = ["**", "__"].into_iter().find(|d| rest.starts_with(d)) {
+            let after = &rest[2..];
+            if style.bold || is_emphasis_open(delimiter, previous, after) {
+                push(spans, &std::mem::take(&mut text), style);
+                style.bold = !style.bold;
+            } else {
+                text.push_str(delimiter);
+            }
+            rest = after;
+            continue;
+        }
+
+        if c == '*' || c == '_' {
+            let delimiter = &rest[..1];
+            let after = &rest[1..];
+            let closes = style.italic && (c == '*' || !starts_with_word_char(after));
+            if closes || (!style.italic && is_emphasis_open(delimiter, previous, after)) {
+                push(spans, &std::mem::take(&mut text), style);
+                style.italic = !style.italic;
+                rest = after;
+                continue;
+            }
+        }
+
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    push(spans, &text, style);
+}
+
+/// Whether `delimiter` opens emphasis: it must be followed by text and closed
+/// later on the line. Underscores inside words (`snake_case`) never do.
+fn is_emphasis_open(delimiter: &str, previous: Option<char>, after: &str) -> bool {
+    if after.is_empty() || after.starts_with(char::is_whitespace) {
+        return false;
+    }
+    if delimiter.starts_with('_') && previous.is_some_and(|c| c.is_alphanumeric()) {
+        return false;
+    }
+    after.contains(delimiter)
+}
+
+fn starts_with_word_char(text: &str) -> bool {
+    text.chars().next().is_some_and(|c| c.is_alphanumeric())
+}
+
+/// Split `[label](url)rest` into the label and the rest
+fn split_link(text: &str) -> Option<(&str, &str)> {
+    let label_end = text.find("](")?;
+    let url_end = label_end + 2 + text[label_end + 2..].find(')')?;
+    Some((&text[1..label_end], &text[url_end + 1..]))
+}
+
+/// Append text, merging it into the last span if the style is the same
+fn push(spans: &mut Vec<Span>, text: &str, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => spans.push(Span {
+            text: text.to_string(),
+            style,
+        }),
+    }
+}
+
+/// Cut the spans to at most `max_len` graphemes, ending with an ellipsis if
+/// anything was cut
+fn truncate(spans: &mut Vec<Span>, max_len: usize) {
+    let total: usize = spans
+        .iter()
+        .map(|span| span.text.graphemes(true).count())
+        .sum();
+    if total <= max_len {
+        return;
+    }
+
+    let mut remaining = max_len.saturating_sub(1);
+    let mut kept = Vec::new();
+    for mut span in spans.drain(..) {
+        if remaining == 0 {
+            break;
+        }
+        let count = span.text.graphemes(true).count();
+        if count > remaining {
+            span.text = span.text.graphemes(true).take(remaining).collect();
+        }
+        remaining -= count.min(remaining);
+        kept.push(span);
+    }
+
+    // Don't leave a dangling space or line break before the ellipsis
+    while let Some(last) = kept.last_mut() {
+        let trimmed_len = last.text.trim_end().len();
+        last.text.truncate(trimmed_len);
+        if !last.text.is_empty() {
+            break;
+        }
+        kept.pop();
+    }
+
+    let style = kept.last().map(|span| span.style).unwrap_or_default();
+    push(&mut kept, ELLIPSIS, style);
+    *spans = kept;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(source: &str) -> String {
+        render_body(source, BodyFormat::PlainText, 1_000)
+    }
+
+    fn markup(source: &str) -> String {
+        render_body(source, BodyFormat::Markup, 1_000)
+    }
+
+    #[test]
+    fn test_plain_text_strips_markdown() {
+        assert_eq!(
+            plain("**Launch** is _today_, see [the plan](https://cushion.so/p) and `npm run`"),
+            "Launch is today, see the plan and npm run"
+        );
+        assert_eq!(plain("~~old~~ new"), "old new");
+        assert_eq!(
+            plain("## Agenda\n- one\n  * two\n> quoted"),
+            "Agenda\n• one\n  • two\nquoted"
+        );
+    }
+
+    #[test]
+    fn test_markup_renders_bold_and_italic() {
+        assert_eq!(
+            markup("**Launch** is *today*"),
+            "<b>Launch</b> is <i>today</i>"
+        );
+        assert_eq!(markup("***both***"), "<b><i>both</i></b>");
+        assert_eq!(markup("# Title"), "<b>Title</b>");
+    }
+
+    #[test]
+    fn test_markup_escapes_html() {
+        assert_eq!(
+            markup("<script> & **a < b** &amp;"),
+            "&lt;script&gt; &amp; <b>a &lt; b</b> &amp;amp;"
+        );
+        assert_eq!(
+            escape_markup("<b>Tom & Jerry</b>"),
+            "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn test_literal_delimiters_are_kept() {
+        assert_eq!(plain("snake_case_name"), "snake_case_name");
+        assert_eq!(plain("2 * 3 = 6"), "2 * 3 = 6");
+        assert_eq!(plain("**unclosed"), "**unclosed");
+        assert_eq!(plain(r"\*not italic\*"), "*not italic*");
+        assert_eq!(plain("[not a link]"), "[not a link]");
+    }
+
+    #[test]
+    fn test_blank_lines_around_body_are_trimmed() {
+        assert_eq!(plain("\n\nhello  \n\nworld\n\n"), "hello\n\nworld");
+        assert_eq!(plain("  \n"), "");
+    }
+
+    #[test]
+    fn test_truncates_on_grapheme_boundaries() {
+        assert_eq!(render_body("abcdef", BodyFormat::PlainText, 6), "abcdef");
+        assert_eq!(render_body("abcdefg", BodyFormat::PlainText, 6), "abcde…");
+
+        // A family emoji and a decomposed "é" are one grapheme each
+        let family = "👨‍👩‍👧";
+        let body = format!("{}e\u{301}{}xyz", family, family);
+        assert_eq!(
+            render_body(&body, BodyFormat::PlainText, 4),
+            format!("{}e\u{301}{}…", family, family)
+        );
+    }
+
+    #[test]
+    fn test_truncation_counts_visible_text_only() {
+        // Markup tags and escapes don't count towards the limit
+        assert_eq!(
+            render_body("**<<<<**", BodyFormat::Markup, 4),
+            "<b>&lt;&lt;&lt;&lt;</b>"
+        );
+        assert_eq!(
+            render_body("**bold** and more", BodyFormat::Markup, 7),
+            "<b>bold</b> a…"
+        );
+        assert_eq!(render_body("one two", BodyFormat::PlainText, 5), "one…");
+    }
+}
@@ -3,9 +3,41 @@
 /// This module provides native notification support with click handlers
 /// for macOS, Windows, and Linux without heavy dependencies.
 
-use tauri::{AppHandle, Emitter, Manager};
-use std::sync::{Arc, Mutex, OnceLock};
-use std::collections::HashMap;
+use crate::url_policy::UrlPolicy;
+use tauri::{AppHandle, Manager};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, SystemTime};
+
+mod backend;
+mod batching;
+mod capabilities;
+#[cfg(any(target_os = "macos", test))]
+mod categories;
+mod history;
+mod images;
+mod markup;
+mod quiet_hours;
+mod scheduler;
+mod sounds;
+mod store;
+
+pub use batching::BatchingSettings;
+pub use capabilities::NotificationCapabilities;
+pub use history::{HistoryEntry, HistoryRetention};
+pub use images::{ImageInput, NotificationImage};
+pub use quiet_hours::QuietHoursPolicy;
+pub use scheduler::ScheduledNotification;
+pub use sounds::{NotificationCategory, NotificationSound, SoundProfiles};
+pub use store::{ActiveNotification, NotificationMetadata};
+use backend::NotificationBackend;
+use batching::Batcher;
+use history::{HistoryOutcome, NotificationHistory};
+use quiet_hours::{QuietHours, SystemClock};
+use scheduler::Scheduler;
+use store::MetadataStore;
 
 #[cfg(target_os = "macos")]
 pub mod macos;
@@ -38,28 +70,282 @@ pub enum ClickAction {
     Dismiss,
     /// User clicked a custom action button
     Button(String),
+    /// User sent an inline reply with the given text
+    Reply(String),
+}
+
+/// A custom action button shown on a notification
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NotificationAction {
+    /// Identifier reported back when the button is pressed
+    pub id: String,
+    /// Button label shown to the user
+    pub label: String,
+}
+
+/// An inline reply field shown on a notification
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NotificationReply {
+    /// Label of the reply button
+    pub label: String,
+    /// Placeholder text shown in the empty reply field
+    pub placeholder: Option<String>,
+}
+
+/// How urgently a notification should interrupt the user
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationPriority {
+    /// FYI updates, shown passively without interrupting
+    Low,
+    #[default]
+    Normal,
+    /// Direct mentions and replies; skips batching and can break through quiet hours
+    ///
+    /// Sent to macOS as time sensitive, which needs the
+    /// `com.apple.developer.usernotifications.time-sensitive` entitlement.
+    /// The app isn't signed with it, so macOS shows these as active, like
+    /// normal notifications, and they don't break through Focus.
+    High,
+    /// Time-critical notifications that stay on screen until handled
+    ///
+    /// On macOS these are sent like `High` ones.
+    Critical,
+}
+
+/// Whether the app may show notifications
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    /// The user hasn't been asked yet (macOS)
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    NotDetermined,
+    /// Notifications are delivered quietly to Notification Center (macOS)
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Provisional,
+}
+
+/// Notification permission as reported by the platform
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPermission {
+    pub status: PermissionStatus,
+    /// Optional features reported by the notification server (Linux)
+    pub capabilities: Vec<String>,
+}
+
+impl NotificationPermission {
+    pub fn new(status: PermissionStatus) -> Self {
+        Self {
+            status,
+            capabilities: Vec::new(),
+        }
+    }
+}
+
+/// A notification to be displayed by the platform backend
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationRequest {
+    /// The notification identifier (`cushion-<uuid>`)
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    /// Optional deep link URL opened when the notification body is clicked
+    pub url: Option<String>,
+    /// Action buttons, in display order
+    pub actions: Vec<NotificationAction>,
+    /// Inline reply field, degraded to a plain "reply" action where unsupported
+    pub reply: Option<NotificationReply>,
+    /// Arbitrary JSON payload kept with the notification's metadata
+    pub payload: Option<serde_json::Value>,
+    /// Thread/group key; notifications of a thread are grouped natively
+    pub thread_id: Option<String>,
+    /// URLs of the notifications summarized by a digest notification
+    pub digest_urls: Vec<String>,
+    /// Avatar or attachment image, already validated and cached
+    pub image: Option<NotificationImage>,
+    /// Sound to play; filled in from the category's profile when `None`
+    pub sound: Option<NotificationSound>,
+    /// Kind of notification, used to pick its sound
+    pub category: Option<NotificationCategory>,
+    /// How urgently to interrupt the user: decides batching and quiet hours,
+    /// and on macOS the interruption level (see `NotificationPriority`)
+    pub priority: NotificationPriority,
+    /// Adds a "Snooze" button that shows the notification again after this
+    /// many minutes
+    pub snooze_minutes: Option<u32>,
+}
+
+/// Payload of the `notification-action` event emitted to the webview
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationActionEvent {
+    /// The notification identifier
+    pub id: String,
+    /// The identifier of the pressed action button
+    pub action_id: String,
+    /// Deep link URL associated with the notification, if any
+    pub url: Option<String>,
+}
+
+/// Payload of the `notification-reply` event emitted to the webview
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationReplyEvent {
+    /// The notification identifier
+    pub id: String,
+    /// The text typed by the user
+    pub text: String,
+    /// Deep link URL associated with the notification, if any
+    pub url: Option<String>,
+}
+
+/// Payload of the `notification-digest` event emitted to the webview
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationDigestEvent {
+    /// The digest notification identifier
+    pub id: String,
+    /// Deep link URLs of the notifications summarized by the digest
+    pub urls: Vec<String>,
+}
+
+/// Action identifier reserved for clicks on the notification body
+const DEFAULT_ACTION_ID: &str = "default";
+
+/// Action identifier reserved for the inline reply action
+const REPLY_ACTION_ID: &str = "reply";
+
+/// Action identifier reserved for the snooze button
+const SNOOZE_ACTION_ID: &str = "snooze";
+
+/// Longest snooze accepted
+const MAX_SNOOZE_MINUTES: u32 = 7 * 24 * 60;
+
+/// Longest thread id accepted (Windows limits toast groups to 64 characters)
+const MAX_THREAD_ID_LEN: usize = 64;
+
+/// Maximum number of notifications whose metadata is kept
+const METADATA_CAPACITY: usize = 500;
+
+/// How long notification metadata is kept after the notification is shown
+const METADATA_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How often held notifications are checked for the end of quiet hours
+const QUIET_HOURS_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long history changes are collected before the log is written, so a
+/// burst of notifications or clicks writes it once
+const HISTORY_SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// What the default click handler does in response to a click
+#[derive(Debug, Clone, PartialEq)]
+enum ClickEvent {
+    /// Body click: focus the window and open the deep link, if it's valid
+    Open(Option<String>),
+    /// Body click on a digest: focus the window and let the webview decide
+    /// what to open
+    Digest(NotificationDigestEvent),
+    Action(NotificationActionEvent),
+    Reply(NotificationReplyEvent),
 }
 
 /// Global notification manager instance
 static NOTIFICATION_MANAGER: OnceLock<Arc<NotificationManager>> = OnceLock::new();
 
+/// Persisted settings and file locations the manager starts with
+#[derive(Default)]
+struct ManagerConfig {
+    policy: QuietHoursPolicy,
+    batching: BatchingSettings,
+    retention: HistoryRetention,
+    sound_profiles: SoundProfiles,
+    url_policy: UrlPolicy,
+    /// Where changed settings are saved
+    config_dir: Option<PathBuf>,
+    history_path: Option<PathBuf>,
+    schedule_path: Option<PathBuf>,
+    image_cache_dir: Option<PathBuf>,
+}
+
 /// Cross-platform notification manager
 pub struct NotificationManager {
-    app: AppHandle,
+    /// Shows and withdraws notifications on this platform
+    backend: Arc<dyn NotificationBackend>,
+    /// Where changed settings are saved
+    config_dir: Option<PathBuf>,
+    /// Decides which notification URLs may be opened
+    url_policy: UrlPolicy,
     callback: Mutex<Option<NotificationCallback>>,
-    /// Store notification metadata (id -> url mapping)
-    metadata: Mutex<HashMap<String, String>>,
+    /// Metadata of shown notifications, dropped once they are handled
+    metadata: Mutex<MetadataStore>,
+    /// Quiet hours policy and the notifications held back by it
+    quiet_hours: Mutex<QuietHours>,
+    /// Collects bursts of notifications into digests
+    batcher: Batcher,
+    /// Log of shown notifications, saved to `history_path`
+    history: Arc<Mutex<NotificationHistory>>,
+    history_path: Option<PathBuf>,
+    /// Set while a history save is scheduled
+    history_save_pending: Arc<AtomicBool>,
+    /// Runs background work such as saving the history log
+    runtime: tokio::runtime::Handle,
+    /// Where validated notification images are written
+    image_cache_dir: Option<PathBuf>,
+    /// Sound for each notification category
+    sound_profiles: Mutex<SoundProfiles>,
+    /// Notifications scheduled for later, including snoozed ones
+    scheduler: Arc<Scheduler>,
+    /// What the notification system supports, once probed successfully
+    capabilities: Mutex<Option<NotificationCapabilities>>,
 }
 
 impl NotificationManager {
     /// Initialize the notification manager
     pub fn init(app: AppHandle) -> Arc<Self> {
-        let manager = Arc::new(NotificationManager {
-            app,
-            callback: Mutex::new(None),
-            metadata: Mutex::new(HashMap::new()),
+        let config = ManagerConfig {
+            policy: crate::storage::load_json(&app, quiet_hours::POLICY_FILE).unwrap_or_default(),
+            batching: crate::storage::load_json(&app, batching::SETTINGS_FILE).unwrap_or_default(),
+            retention: crate::storage::load_json(&app, history::RETENTION_FILE).unwrap_or_default(),
+            sound_profiles: crate::storage::load_json(&app, sounds::PROFILES_FILE).unwrap_or_default(),
+            url_policy: app.state::<UrlPolicy>().inner().clone(),
+            config_dir: app.path().app_config_dir().ok(),
+            history_path: crate::storage::data_path(&app, history::HISTORY_FILE).ok(),
+            schedule_path: crate::storage::data_path(&app, scheduler::SCHEDULE_FILE).ok(),
+            image_cache_dir: app.path().app_cache_dir().ok().map(|dir| dir.join(images::CACHE_DIR)),
+        };
+
+        #[cfg(target_os = "macos")]
+        let backend = Arc::new(macos::MacosBackend);
+
+        #[cfg(target_os = "windows")]
+        let backend = Arc::new(windows::WindowsBackend { app: app.clone() });
+
+        #[cfg(target_os = "linux")]
+        let backend = Arc::new(linux::LinuxBackend {
+            resource_dir: app.path().resource_dir().ok(),
         });
 
+        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+        let backend = Arc::new(backend::UnsupportedBackend);
+
+        let runtime = tauri::async_runtime::handle().inner().clone();
+        let manager = Self::new(backend, config, runtime);
+
+        // Images of scheduled notifications are kept however old they are
+        if let Some(ref dir) = manager.image_cache_dir {
+            let scheduled: Vec<PathBuf> = manager
+                .scheduler
+                .list()
+                .into_iter()
+                .filter_map(|scheduled| scheduled.request.image.map(|image| image.path))
+                .collect();
+            images::prune_cache(dir, images::CACHE_TTL, SystemTime::now(), &scheduled);
+        }
+
         // Set up platform-specific handlers
         #[cfg(target_os = "macos")]
         macos::setup(manager.clone());
@@ -70,10 +356,80 @@ impl NotificationManager {
         #[cfg(target_os = "linux")]
         linux::setup(manager.clone());
 
+        // Deliver notifications held during quiet hours once they end
+        let digest_manager = manager.clone();
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(QUIET_HOURS_CHECK_INTERVAL).await;
+                if let Err(e) = digest_manager.deliver_held_notifications() {
+                    eprintln!("❌ Failed to show quiet hours digest: {}", e);
+                }
+            }
+        });
+
+        // Show scheduled notifications as they come due
+        let scheduler = manager.scheduler.clone();
+        let weak = Arc::downgrade(&manager);
+        tauri::async_runtime::spawn(async move {
+            scheduler
+                .run(move |request| {
+                    let Some(manager) = weak.upgrade() else { return };
+                    println!("⏰ Showing scheduled notification: {}", request.id);
+                    if let Err(e) = manager.show_notification(request) {
+                        eprintln!("❌ Failed to show scheduled notification: {}", e);
+                    }
+                })
+                .await;
+        });
+
         NOTIFICATION_MANAGER.set(manager.clone()).ok();
         manager
     }
 
+    /// Create a manager showing notifications through `backend`
+    ///
+    /// Batched notifications are delivered, and the history log saved, on
+    /// `runtime`.
+    fn new(
+        backend: Arc<dyn NotificationBackend>,
+        config: ManagerConfig,
+        runtime: tokio::runtime::Handle,
+    ) -> Arc<Self> {
+        let history = match config.history_path {
+            Some(ref path) => NotificationHistory::load(path, config.retention),
+            None => NotificationHistory::new(Vec::new(), config.retention),
+        };
+        let scheduler = Scheduler::load(config.schedule_path, Arc::new(SystemClock));
+
+        Arc::new_cyclic(|weak: &Weak<NotificationManager>| {
+            let weak = weak.clone();
+            let batcher = Batcher::new(runtime.clone(), config.batching, move |request| {
+                let Some(manager) = weak.upgrade() else { return };
+                if let Err(e) = manager.deliver(request) {
+                    eprintln!("❌ Failed to show notification: {}", e);
+                }
+            });
+
+            NotificationManager {
+                backend,
+                config_dir: config.config_dir,
+                url_policy: config.url_policy,
+                callback: Mutex::new(None),
+                metadata: Mutex::new(MetadataStore::new(METADATA_CAPACITY, METADATA_TTL)),
+                quiet_hours: Mutex::new(QuietHours::new(config.policy, Arc::new(SystemClock))),
+                batcher,
+                history: Arc::new(Mutex::new(history)),
+                history_path: config.history_path,
+                history_save_pending: Arc::new(AtomicBool::new(false)),
+                runtime,
+                image_cache_dir: config.image_cache_dir,
+                sound_profiles: Mutex::new(config.sound_profiles),
+                scheduler: Arc::new(scheduler),
+                capabilities: Mutex::new(None),
+            }
+        })
+    }
+
     /// Get the global notification manager instance
     pub fn get() -> Option<Arc<NotificationManager>> {
         NOTIFICATION_MANAGER.get().cloned()
@@ -92,110 +448,942 @@ impl NotificationManager {
     pub fn handle_click(&self, click: NotificationClick) {
         println!("🔔 Notification clicked: {:?}", click);
 
-        if let Some(callback) = self.callback.lock().unwrap().as_ref() {
+        let id = click.id.clone();
+        let outcome = match click.action {
+            ClickAction::Dismiss => HistoryOutcome::Dismissed,
+            _ => HistoryOutcome::Clicked,
+        };
+
+        // Snoozing is handled here rather than by the webview
+        if matches!(click.action, ClickAction::Button(ref action_id) if action_id == SNOOZE_ACTION_ID) {
+            if let Err(e) = self.snooze(&id) {
+                eprintln!("❌ Failed to snooze notification: {}", e);
+            }
+        } else if let Some(callback) = self.callback.lock().unwrap().as_ref() {
             callback(click);
         }
+
+        self.record_outcome(&id, outcome);
+
+        // The click or dismissal is handled, so the metadata is no longer needed
+        self.metadata.lock().unwrap().remove(&id);
     }
 
     /// Store metadata for a notification
-    pub fn store_metadata(&self, id: String, url: String) {
-        let mut metadata = self.metadata.lock().unwrap();
-        metadata.insert(id, url);
+    pub fn store_metadata(&self, id: String, metadata: NotificationMetadata) {
+        let mut store = self.metadata.lock().unwrap();
+        store.insert(id, metadata, SystemTime::now());
     }
 
     /// Get metadata for a notification
-    pub fn get_metadata(&self, id: &str) -> Option<String> {
-        let metadata = self.metadata.lock().unwrap();
-        metadata.get(id).cloned()
+    pub fn get_metadata(&self, id: &str) -> Option<NotificationMetadata> {
+        let mut store = self.metadata.lock().unwrap();
+        store.get(id, SystemTime::now())
+    }
+
+    /// Get the deep link URL stored for a notification
+    pub fn get_url(&self, id: &str) -> Option<String> {
+        self.get_metadata(id).and_then(|metadata| metadata.url)
+    }
+
+    /// List the notifications whose metadata is still tracked
+    pub fn list_active_notifications(&self) -> Vec<ActiveNotification> {
+        let mut store = self.metadata.lock().unwrap();
+        store.active(SystemTime::now())
     }
 
     /// Show a notification
-    pub fn show_notification(
-        &self,
-        id: String,
-        title: String,
-        body: String,
-        url: Option<String>,
-    ) -> Result<(), String> {
-        println!("📱 Showing notification: '{}' - '{}'", title, body);
+    ///
+    /// Returns the notification id so the webview can dismiss it later. A
+    /// notification folded into a batching digest is never shown under that
+    /// id, so dismissing it does nothing; see the `batching` module.
+    /// During quiet hours the notification is held and shown later as part of
+    /// a digest, unless its priority breaks through. Otherwise it goes through
+    /// the batcher, which may fold it into a digest with others arriving at the
+    /// same time; high priority notifications are shown right away. Either
+    /// way it's recorded in the history log now, under its own id.
+    pub fn show_notification(&self, request: NotificationRequest) -> Result<String, String> {
+        println!("📱 Showing notification: '{}' - '{}'", request.title, request.body);
+
+        validate_request(&request)?;
+
+        self.record_history(&request, store::unix_millis(SystemTime::now()));
 
-        // Store metadata if URL provided
-        if let Some(ref notification_url) = url {
-            self.store_metadata(id.clone(), notification_url.clone());
+        let id = request.id.clone();
+        let Some(request) = self.quiet_hours.lock().unwrap().filter(request) else {
+            println!("🌙 Holding notification during quiet hours: {}", id);
+            return Ok(id);
+        };
+
+        if request.priority >= NotificationPriority::High {
+            self.deliver(request)?;
+        } else {
+            self.batcher.push(request);
         }
+        Ok(id)
+    }
 
-        // Platform-specific notification display
-        #[cfg(target_os = "macos")]
-        return macos::show_notification(id, title, body, url);
+    /// Give `request` its own copy of its image, so showing another
+    /// notification with the same image can't use it up first
+    ///
+    /// If the image can't be copied the notification is kept without it.
+    fn with_own_image(&self, mut request: NotificationRequest) -> NotificationRequest {
+        let (Some(dir), Some(image)) = (&self.image_cache_dir, &request.image) else {
+            return request;
+        };
+        match images::copy_image(dir, image) {
+            Ok(copy) => request.image = Some(copy),
+            Err(e) => {
+                eprintln!("⚠️ {}, keeping notification {} without it", e, request.id);
+                request.image = None;
+            }
+        }
+        request
+    }
 
-        #[cfg(target_os = "windows")]
-        return windows::show_notification(self.app.clone(), id, title, body, url);
+    /// Validate an image from the webview and write it to the image cache
+    pub fn cache_image(&self, input: &ImageInput) -> Result<NotificationImage, String> {
+        let cache_dir = self
+            .image_cache_dir
+            .as_ref()
+            .ok_or_else(|| "Notification image cache unavailable".to_string())?;
+        images::cache_image(cache_dir, input)
+    }
 
-        #[cfg(target_os = "linux")]
-        return linux::show_notification(id, title, body, url);
+    /// Hand a notification to the platform backend
+    fn deliver(&self, mut request: NotificationRequest) -> Result<(), String> {
+        // Keep the request as given so snoozing can show it again; the image
+        // is copied now, since macOS moves the original away when it's shown
+        let snooze_request = request
+            .snooze_minutes
+            .map(|_| self.with_own_image(request.clone()));
+        if snooze_request.is_some() {
+            request.actions.push(NotificationAction {
+                id: SNOOZE_ACTION_ID.to_string(),
+                label: "Snooze".to_string(),
+            });
+        }
 
-        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-        {
-            Err("Notifications not supported on this platform".to_string())
+        let sound = self
+            .sound_profiles
+            .lock()
+            .unwrap()
+            .resolve(request.sound.as_ref(), request.category);
+        request.sound = Some(sound);
+
+        let now = store::unix_millis(SystemTime::now());
+        self.store_metadata(
+            request.id.clone(),
+            NotificationMetadata {
+                url: request.url.clone(),
+                created_at: now,
+                thread_key: request.thread_id.clone(),
+                payload: request.payload.clone(),
+                digest_urls: request.digest_urls.clone(),
+                snooze_request,
+            },
+        );
+
+        self.backend.show(&request, &self.capabilities())
+    }
+
+    /// Show the notifications held during quiet hours as a digest, if they're over
+    fn deliver_held_notifications(&self) -> Result<(), String> {
+        let held = self.quiet_hours.lock().unwrap().take_held();
+        match held.and_then(quiet_hours::digest) {
+            Some(digest) => {
+                println!("🌅 Quiet hours over, showing digest: {}", digest.title);
+                self.deliver(digest)
+            }
+            None => Ok(()),
         }
     }
 
-    /// Get app handle
-    pub fn app(&self) -> &AppHandle {
-        &self.app
+    /// Get the quiet hours policy
+    pub fn quiet_hours_policy(&self) -> QuietHoursPolicy {
+        self.quiet_hours.lock().unwrap().policy().clone()
+    }
+
+    /// Replace and persist the quiet hours policy
+    pub fn set_quiet_hours_policy(&self, policy: QuietHoursPolicy) -> Result<(), String> {
+        for thread_id in &policy.allowlist {
+            validate_thread_id(thread_id)?;
+        }
+
+        self.quiet_hours.lock().unwrap().set_policy(policy);
+        self.save_quiet_hours_policy()?;
+        self.deliver_held_notifications()
+    }
+
+    /// Pause notifications for the given duration
+    pub fn pause_notifications(&self, duration: Duration) -> Result<(), String> {
+        println!("🌙 Pausing notifications for {} minute(s)", duration.as_secs() / 60);
+        self.quiet_hours.lock().unwrap().pause(duration);
+        self.save_quiet_hours_policy()
+    }
+
+    /// End a manual pause, showing anything held during it
+    pub fn resume_notifications(&self) -> Result<(), String> {
+        println!("🔔 Resuming notifications");
+        self.quiet_hours.lock().unwrap().resume();
+        self.save_quiet_hours_policy()?;
+        self.deliver_held_notifications()
+    }
+
+    fn save_quiet_hours_policy(&self) -> Result<(), String> {
+        let policy = self.quiet_hours_policy();
+        self.save_setting(quiet_hours::POLICY_FILE, &policy)
+    }
+
+    /// Save a setting as JSON in the app config directory
+    fn save_setting<T: Serialize>(&self, file_name: &str, value: &T) -> Result<(), String> {
+        let dir = self
+            .config_dir
+            .as_ref()
+            .ok_or_else(|| "App config directory unavailable".to_string())?;
+        crate::storage::write_json(&dir.join(file_name), value)
+    }
+
+    /// Add a notification to the history log
+    ///
+    /// Digests aren't recorded; the notifications folded into them already are.
+    fn record_history(&self, request: &NotificationRequest, now: u64) {
+        let mut history = self.history.lock().unwrap();
+        history.record(
+            HistoryEntry {
+                id: request.id.clone(),
+                title: request.title.clone(),
+                body: request.body.clone(),
+                url: request.url.clone(),
+                shown_at: now,
+                outcome: HistoryOutcome::Shown,
+            },
+            now,
+        );
+        drop(history);
+        self.save_history_soon();
+    }
+
+    /// Record what the user did with a notification in the history log
+    fn record_outcome(&self, id: &str, outcome: HistoryOutcome) {
+        let found = self.history.lock().unwrap().set_outcome(id, outcome);
+        if found {
+            self.save_history_soon();
+        }
+    }
+
+    /// Save the history log in the background after `HISTORY_SAVE_DELAY`,
+    /// unless a save is already scheduled
+    fn save_history_soon(&self) {
+        let Some(path) = self.history_path.clone() else { return };
+        if self.history_save_pending.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let history = self.history.clone();
+        let pending = self.history_save_pending.clone();
+        self.runtime.spawn(async move {
+            tokio::time::sleep(HISTORY_SAVE_DELAY).await;
+            let _ = tokio::task::spawn_blocking(move || {
+                // Changes from here on schedule another save
+                pending.store(false, Ordering::SeqCst);
+                save_history(&history, &path);
+            })
+            .await;
+        });
+    }
+
+    /// Show the notifications held in an open batching window now, e.g.
+    /// before quitting
+    pub fn flush_batches(&self) {
+        self.batcher.flush();
+    }
+
+    /// Write the history log now if a save is scheduled, e.g. before quitting
+    pub fn flush_history(&self) {
+        let Some(ref path) = self.history_path else { return };
+        if self.history_save_pending.swap(false, Ordering::SeqCst) {
+            save_history(&self.history, path);
+        }
+    }
+
+    /// Search the history log, newest first
+    pub fn notification_history(&self, query: &str, limit: usize, offset: usize) -> Vec<HistoryEntry> {
+        self.history.lock().unwrap().search(query, limit, offset)
+    }
+
+    /// Remove every entry from the history log
+    pub fn clear_notification_history(&self) -> Result<(), String> {
+        println!("🧹 Clearing notification history");
+        let mut history = self.history.lock().unwrap();
+        history.clear();
+        match self.history_path {
+            Some(ref path) => history.save(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the history retention settings
+    pub fn history_retention(&self) -> HistoryRetention {
+        self.history.lock().unwrap().retention().clone()
     }
-}
 
-/// Validate that a URL is safe to emit as a deep link
-fn is_valid_notification_url(url: &str) -> bool {
-    if let Ok(parsed) = url::Url::parse(url) {
-        // Allow cushion:// and cushion-dev:// deep links
-        if parsed.scheme() == "cushion" || parsed.scheme() == "cushion-dev" {
-            return true;
+    /// Replace and persist the history retention settings
+    pub fn set_history_retention(&self, retention: HistoryRetention) -> Result<(), String> {
+        if retention.max_age_days == 0 || retention.max_entries == 0 {
+            return Err("History retention must keep at least one day and one entry".to_string());
         }
-        // Allow HTTPS URLs to cushion.so domains
-        if parsed.scheme() == "https" {
-            if let Some(host) = parsed.host_str() {
-                return host == "app.cushion.so" || host.ends_with(".cushion.so");
+
+        self.save_setting(history::RETENTION_FILE, &retention)?;
+        self.history
+            .lock()
+            .unwrap()
+            .set_retention(retention, store::unix_millis(SystemTime::now()));
+        self.save_history_soon();
+        Ok(())
+    }
+
+    /// Schedule a notification to be shown at `at` (milliseconds since the
+    /// Unix epoch), returning its id
+    ///
+    /// Fails once `scheduler::MAX_SCHEDULED` notifications are pending.
+    pub fn schedule_notification(&self, at: u64, request: NotificationRequest) -> Result<String, String> {
+        validate_request(&request)?;
+
+        println!("⏰ Scheduling notification {} for {}", request.id, at);
+        let id = request.id.clone();
+        let request = self.with_own_image(request);
+        self.scheduler.schedule(ScheduledNotification { at, request })?;
+        Ok(id)
+    }
+
+    /// Cancel a scheduled notification
+    pub fn cancel_scheduled_notification(&self, id: &str) -> Result<(), String> {
+        if self.scheduler.cancel(id)? {
+            println!("⏰ Cancelled scheduled notification: {}", id);
+            Ok(())
+        } else {
+            Err(format!("No scheduled notification with id {}", id))
+        }
+    }
+
+    /// List the scheduled notifications, soonest first
+    pub fn list_scheduled_notifications(&self) -> Vec<ScheduledNotification> {
+        self.scheduler.list()
+    }
+
+    /// Show a notification again after its snooze time
+    fn snooze(&self, id: &str) -> Result<(), String> {
+        let request = self
+            .get_metadata(id)
+            .and_then(|metadata| metadata.snooze_request)
+            .ok_or_else(|| format!("Notification {} can't be snoozed", id))?;
+        let minutes = request.snooze_minutes.unwrap_or_default();
+
+        println!("💤 Snoozing notification {} for {} minute(s)", id, minutes);
+        let at = store::unix_millis(SystemTime::now()) + u64::from(minutes) * 60 * 1000;
+        self.scheduler.schedule(ScheduledNotification { at, request })
+    }
+
+    /// Get what the notification system supports
+    ///
+    /// The backend is asked on first use; on Linux that probes the
+    /// notification server over DBus, so the lock isn't held meanwhile and
+    /// two early callers may both probe. A failed probe isn't cached, so a
+    /// server started later is picked up; until then
+    /// [`NotificationCapabilities::assumed`] is used.
+    pub fn capabilities(&self) -> NotificationCapabilities {
+        if let Some(ref capabilities) = *self.capabilities.lock().unwrap() {
+            return capabilities.clone();
+        }
+
+        match self.backend.capabilities() {
+            Ok(capabilities) => {
+                *self.capabilities.lock().unwrap() = Some(capabilities.clone());
+                capabilities
+            }
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                NotificationCapabilities::assumed()
             }
         }
     }
-    false
+
+    /// Get the sound profiles
+    pub fn sound_profiles(&self) -> SoundProfiles {
+        self.sound_profiles.lock().unwrap().clone()
+    }
+
+    /// Replace and persist the sound profiles
+    pub fn set_sound_profiles(&self, profiles: SoundProfiles) -> Result<(), String> {
+        profiles.validate()?;
+        self.save_setting(sounds::PROFILES_FILE, &profiles)?;
+        *self.sound_profiles.lock().unwrap() = profiles;
+        Ok(())
+    }
+
+    /// Get the batching settings
+    pub fn batching_settings(&self) -> BatchingSettings {
+        self.batcher.settings()
+    }
+
+    /// Replace and persist the batching settings
+    pub fn set_batching_settings(&self, settings: BatchingSettings) -> Result<(), String> {
+        if settings.window_ms > batching::MAX_WINDOW_MS {
+            return Err(format!(
+                "Batching window must be at most {} ms",
+                batching::MAX_WINDOW_MS
+            ));
+        }
+        if settings.threshold < 2 {
+            return Err("Batching threshold must be at least 2".to_string());
+        }
+
+        self.save_setting(batching::SETTINGS_FILE, &settings)?;
+        self.batcher.set_settings(settings);
+        Ok(())
+    }
+
+    /// Remove all notifications of a thread, e.g. once it's read in the webview
+    pub fn clear_notifications_for_thread(&self, thread_id: &str) -> Result<(), String> {
+        validate_thread_id(thread_id)?;
+
+        let ids = self.metadata.lock().unwrap().remove_thread(thread_id);
+        println!("🧹 Clearing {} notification(s) for thread: {}", ids.len(), thread_id);
+
+        self.backend.clear_thread(thread_id, &ids)
+    }
+
+    /// Withdraw a shown notification, e.g. once it's read on another device
+    pub fn dismiss_notification(&self, id: &str) -> Result<(), String> {
+        println!("🧹 Dismissing notification: {}", id);
+
+        // Windows needs the thread to find the toast's group
+        let thread_key = self.metadata.lock().unwrap().remove(id).and_then(|m| m.thread_key);
+        self.backend.dismiss(id, thread_key.as_deref())
+    }
+
+    /// Withdraw all of our shown notifications
+    pub fn dismiss_all_notifications(&self) -> Result<(), String> {
+        println!("🧹 Dismissing all notifications");
+
+        self.metadata.lock().unwrap().clear();
+        self.backend.dismiss_all()
+    }
+
+    /// Get the ids of our notifications the OS is still showing
+    pub fn get_delivered_notifications(&self) -> Result<Vec<String>, String> {
+        self.backend.delivered()
+    }
+
+    /// Get the notification permission status without prompting the user
+    pub fn get_permission_status(&self) -> Result<NotificationPermission, String> {
+        self.backend.permission_status()
+    }
+
+    /// Ask the user for permission to show notifications
+    ///
+    /// Only macOS has a permission prompt; elsewhere this returns the current
+    /// status.
+    pub fn request_permission(&self) -> Result<NotificationPermission, String> {
+        self.backend.request_permission()
+    }
 }
 
-/// Setup notification system with default click handler
-pub fn setup(app: &AppHandle) -> Arc<NotificationManager> {
-    let manager = NotificationManager::init(app.clone());
-    let app_clone = app.clone();
+/// Validate a notification from the webview before it's shown or scheduled
+fn validate_request(request: &NotificationRequest) -> Result<(), String> {
+    validate_actions(&request.actions)?;
+    if let Some(ref thread_id) = request.thread_id {
+        validate_thread_id(thread_id)?;
+    }
+    if let Some(ref sound) = request.sound {
+        sound.validate()?;
+    }
+    if let Some(minutes) = request.snooze_minutes {
+        validate_snooze_minutes(minutes)?;
+    }
+    Ok(())
+}
 
-    // Set up default click handler
-    manager.set_callback(move |click| {
-        println!("🔔 Default handler: {:?}", click);
+/// Validate action buttons before handing them to a platform backend
+fn validate_actions(actions: &[NotificationAction]) -> Result<(), String> {
+    for (index, action) in actions.iter().enumerate() {
+        if action.id.is_empty() || action.label.is_empty() {
+            return Err("Notification actions need a non-empty id and label".to_string());
+        }
+        if action.id == DEFAULT_ACTION_ID
+            || action.id == REPLY_ACTION_ID
+            || action.id == SNOOZE_ACTION_ID
+            || action.id.starts_with("com.apple.")
+        {
+            return Err(format!("Notification action id '{}' is reserved", action.id));
+        }
+        if actions[..index].iter().any(|other| other.id == action.id) {
+            return Err(format!("Duplicate notification action id '{}'", action.id));
+        }
+    }
+    Ok(())
+}
 
-        match click.action {
-            ClickAction::Body | ClickAction::Button(_) => {
-                // Emit deep link event if URL exists and is valid
-                if let Some(ref url) = click.url {
-                    if is_valid_notification_url(url) {
-                        println!("🔗 Emitting deep link: {}", url);
-                        let _ = app_clone.emit("deep-link", url.clone());
-                    } else {
-                        eprintln!("🚫 Rejected invalid notification URL: {}", url);
-                    }
+/// Validate a thread id before it's used as a native group key
+fn validate_thread_id(thread_id: &str) -> Result<(), String> {
+    if thread_id.is_empty() || thread_id.len() > MAX_THREAD_ID_LEN {
+        return Err(format!(
+            "Thread id must be between 1 and {} characters",
+            MAX_THREAD_ID_LEN
+        ));
+    }
+    Ok(())
+}
+
+/// Validate a snooze time
+fn validate_snooze_minutes(minutes: u32) -> Result<(), String> {
+    if minutes == 0 || minutes > MAX_SNOOZE_MINUTES {
+        return Err(format!(
+            "Snooze time must be between 1 and {} minutes",
+            MAX_SNOOZE_MINUTES
+        ));
+    }
+    Ok(())
+}
+
+/// Decide what the default click handler does with a click
+fn route_click(manager: &NotificationManager, click: NotificationClick) -> Option<ClickEvent> {
+    match click.action {
+        ClickAction::Body => {
+            let digest_urls = manager
+                .get_metadata(&click.id)
+                .map(|metadata| metadata.digest_urls)
+                .unwrap_or_default();
+
+            if !digest_urls.is_empty() {
+                let urls = digest_urls
+                    .into_iter()
+                    .filter(|url| manager.url_policy.is_notification_url(url))
+                    .collect();
+                return Some(ClickEvent::Digest(NotificationDigestEvent { id: click.id, urls }));
+            }
+
+            let url = click.url.filter(|url| {
+                let valid = manager.url_policy.is_notification_url(url);
+                if !valid {
+                    eprintln!("🚫 Rejected invalid notification URL: {}", url);
                 }
+                valid
+            });
+            Some(ClickEvent::Open(url))
+        }
+        ClickAction::Button(action_id) => Some(ClickEvent::Action(NotificationActionEvent {
+            id: click.id,
+            action_id,
+            url: click.url,
+        })),
+        ClickAction::Reply(text) => Some(ClickEvent::Reply(NotificationReplyEvent {
+            id: click.id,
+            text,
+            url: click.url,
+        })),
+        ClickAction::Dismiss => {
+            println!("Notification dismissed");
+            None
+        }
+    }
+}
 
-                // Focus the window
-                if let Some(window) = app_clone.get_webview_window("main") {
-                    let _ = window.show();
-                    let _ = window.set_focus();
-                    let _ = window.unminimize();
+/// Emit a click to the webview
+fn emit_click_event(app: &AppHandle, event: ClickEvent) {
+    match event {
+        ClickEvent::Open(url) => {
+            match url {
+                // Routed like a link opened from outside, so native routes
+                // such as cushion://update are handled here and ones the
+                // router doesn't know still reach the webview as `deep-link`
+                Some(url) if app.state::<UrlPolicy>().is_deep_link(&url) => {
+                    crate::deep_link::open(app, &url);
                 }
+                Some(url) => {
+                    println!("🔗 Emitting deep link: {}", url);
+                    crate::frontend::emit(app, "deep-link", url);
+                }
+                None => {}
             }
-            ClickAction::Dismiss => {
-                println!("Notification dismissed");
-            }
+            focus_main_window(app);
+        }
+        ClickEvent::Digest(digest) => {
+            println!("📦 Emitting notification digest with {} URL(s)", digest.urls.len());
+            crate::frontend::emit(app, "notification-digest", digest);
+            focus_main_window(app);
+        }
+        ClickEvent::Action(action) => {
+            // Buttons act in place (mark as read, snooze...) so the window is
+            // left alone and the webview decides what to do
+            println!("🔘 Emitting notification action: {}", action.action_id);
+            crate::frontend::emit(app, "notification-action", action);
+        }
+        ClickEvent::Reply(reply) => {
+            println!("💬 Emitting notification reply for: {}", reply.id);
+            crate::frontend::emit(app, "notification-reply", reply);
+        }
+    }
+}
+
+/// Write a snapshot of the history log, without holding its lock while writing
+fn save_history(history: &Mutex<NotificationHistory>, path: &Path) {
+    let snapshot = history.lock().unwrap().clone();
+    if let Err(e) = snapshot.save(path) {
+        eprintln!("❌ Failed to save notification history: {}", e);
+    }
+}
+
+fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = window.unminimize();
+    }
+}
+
+/// Install the default click handler, passing what it decides to `emit`
+fn set_default_handler<F>(manager: &Arc<NotificationManager>, emit: F)
+where
+    F: Fn(ClickEvent) + Send + Sync + 'static,
+{
+    // The manager owns the callback, so it's only borrowed weakly here
+    let weak = Arc::downgrade(manager);
+    manager.set_callback(move |click| {
+        println!("🔔 Default handler: {:?}", click);
+        let Some(manager) = weak.upgrade() else { return };
+        if let Some(event) = route_click(&manager, click) {
+            emit(event);
         }
     });
+}
 
+/// Setup notification system with default click handler
+pub fn setup(app: &AppHandle) -> Arc<NotificationManager> {
+    let manager = NotificationManager::init(app.clone());
+    let app = app.clone();
+    set_default_handler(&manager, move |event| emit_click_event(&app, event));
     manager
 }
+
+#[cfg(test)]
+mod tests {
+    use super::backend::{BackendCall, RecordingBackend};
+    use super::*;
+
+    /// A manager showing notifications through a recording backend, with the
+    /// default click handler collecting what it would emit
+    fn manager(backend: &Arc<RecordingBackend>) -> (Arc<NotificationManager>, Arc<Mutex<Vec<ClickEvent>>>) {
+        let manager = NotificationManager::new(
+            backend.clone(),
+            ManagerConfig::default(),
+            tokio::runtime::Handle::current(),
+        );
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        set_default_handler(&manager, move |event| sink.lock().unwrap().push(event));
+        (manager, events)
+    }
+
+    fn recording_backend() -> Arc<RecordingBackend> {
+        Arc::new(RecordingBackend::new(NotificationCapabilities {
+            actions: true,
+            ..Default::default()
+        }))
+    }
+
+    /// A notification shown right away, skipping the batcher
+    fn request(id: &str, url: Option<&str>) -> NotificationRequest {
+        NotificationRequest {
+            id: id.to_string(),
+            title: format!("Title {}", id),
+            body: "Body".to_string(),
+            url: url.map(str::to_string),
+            priority: NotificationPriority::High,
+            ..Default::default()
+        }
+    }
+
+    fn events(events: &Mutex<Vec<ClickEvent>>) -> Vec<ClickEvent> {
+        events.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_click_on_unrouted_deep_link_reaches_the_webview() {
+        use crate::deep_link::{self, DeepLink};
+
+        let backend = recording_backend();
+        let (manager, emitted) = manager(&backend);
+
+        manager.show_notification(request("cushion-1", Some("cushion://chat/1"))).unwrap();
+        backend.click(&manager, "cushion-1", ClickAction::Body);
+        let [ClickEvent::Open(Some(url))] = &events(&emitted)[..] else {
+            panic!("the click didn't open its URL");
+        };
+
+        // Not a route the router knows, so it's passed on as `deep-link`
+        // rather than dropped
+        assert_eq!(
+            deep_link::parse(&UrlPolicy::default(), url),
+            Ok(DeepLink::Unrouted)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_body_click_opens_deep_link() {
+        let backend = recording_backend();
+        let (manager, emitted) = manager(&backend);
+
+        manager.show_notification(request("cushion-1", Some("cushion://chat/1"))).unwrap();
+        assert_eq!(backend.shown().len(), 1);
+
+        backend.click(&manager, "cushion-1", ClickAction::Body);
+        assert_eq!(
+            events(&emitted),
+            vec![ClickEvent::Open(Some("cushion://chat/1".to_string()))]
+        );
+
+        // The click is recorded and the metadata released
+        let history = manager.notification_history("", 10, 0);
+        assert_eq!(history[0].outcome, HistoryOutcome::Clicked);
+        assert!(manager.get_metadata("cushion-1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_body_click_rejects_invalid_url() {
+        let backend = recording_backend();
+        let (manager, emitted) = manager(&backend);
+
+        manager.show_notification(request("cushion-1", Some("https://evil.example"))).unwrap();
+        backend.click(&manager, "cushion-1", ClickAction::Body);
+
+        // The window is still focused, but nothing is opened
+        assert_eq!(events(&emitted), vec![ClickEvent::Open(None)]);
+    }
+
+    #[tokio::test]
+    async fn test_button_and_reply_are_emitted_with_url() {
+        let backend = recording_backend();
+        let (manager, emitted) = manager(&backend);
+
+        let mut with_button = request("cushion-1", Some("cushion://chat/1"));
+        with_button.actions = vec![NotificationAction {
+            id: "mark-read".to_string(),
+            label: "Mark as read".to_string(),
+        }];
+        manager.show_notification(with_button).unwrap();
+        manager.show_notification(request("cushion-2", None)).unwrap();
+
+        backend.click(&manager, "cushion-1", ClickAction::Button("mark-read".to_string()));
+        backend.click(&manager, "cushion-2", ClickAction::Reply("On my way".to_string()));
+
+        assert_eq!(
+            events(&emitted),
+            vec![
+                ClickEvent::Action(NotificationActionEvent {
+                    id: "cushion-1".to_string(),
+                    action_id: "mark-read".to_string(),
+                    url: Some("cushion://chat/1".to_string()),
+                }),
+                ClickEvent::Reply(NotificationReplyEvent {
+                    id: "cushion-2".to_string(),
+                    text: "On my way".to_string(),
+                    url: None,
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dismissal_emits_nothing() {
+        let backend = recording_backend();
+        let (manager, emitted) = manager(&backend);
+
+        manager.show_notification(request("cushion-1", None)).unwrap();
+        backend.click(&manager, "cushion-1", ClickAction::Dismiss);
+
+        assert!(events(&emitted).is_empty());
+        let history = manager.notification_history("", 10, 0);
+        assert_eq!(history[0].outcome, HistoryOutcome::Dismissed);
+    }
+
+    #[tokio::test]
+    async fn test_snooze_reschedules_without_emitting() {
+        let backend = recording_backend();
+        let (manager, emitted) = manager(&backend);
+
+        let mut snoozable = request("cushion-1", None);
+        snoozable.snooze_minutes = Some(10);
+        manager.show_notification(snoozable.clone()).unwrap();
+
+        let shown = &backend.shown()[0];
+        assert_eq!(shown.actions.last().unwrap().id, SNOOZE_ACTION_ID);
+
+        backend.click(&manager, "cushion-1", ClickAction::Button(SNOOZE_ACTION_ID.to_string()));
+        assert!(events(&emitted).is_empty());
+
+        let scheduled = manager.list_scheduled_notifications();
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].request, snoozable);
+    }
+
+    #[tokio::test]
+    async fn test_snoozed_notification_keeps_its_own_image() {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let dir = std::env::temp_dir().join(format!("cushion-snooze-images-{}", uuid::Uuid::new_v4()));
+        let backend = recording_backend();
+        let config = ManagerConfig {
+            image_cache_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        let manager = NotificationManager::new(backend.clone(), config, tokio::runtime::Handle::current());
+
+        let png = STANDARD.encode(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        let image = manager
+            .cache_image(&ImageInput {
+                source: format!("data:image/png;base64,{}", png),
+                kind: Default::default(),
+            })
+            .unwrap();
+
+        let mut snoozable = request("cushion-1", None);
+        snoozable.snooze_minutes = Some(10);
+        snoozable.image = Some(image.clone());
+        manager.show_notification(snoozable).unwrap();
+
+        // macOS moves the shown image into its own store
+        std::fs::remove_file(&image.path).unwrap();
+        backend.click(&manager, "cushion-1", ClickAction::Button(SNOOZE_ACTION_ID.to_string()));
+
+        let scheduled = manager.list_scheduled_notifications();
+        let snoozed_image = scheduled[0].request.image.as_ref().unwrap();
+        assert_ne!(snoozed_image.path, image.path);
+        assert!(snoozed_image.path.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batched_digest_click_lists_valid_urls() {
+        let backend = recording_backend();
+        let (manager, emitted) = manager(&backend);
+
+        for (id, url) in [
+            ("cushion-0", "cushion://chat/0"),
+            ("cushion-1", "cushion://chat/1"),
+            ("cushion-2", "https://evil.example"),
+            ("cushion-3", "cushion://chat/3"),
+        ] {
+            let mut burst = request(id, Some(url));
+            burst.priority = NotificationPriority::Normal;
+            manager.show_notification(burst).unwrap();
+        }
+        // The first of the burst is shown right away
+        assert_eq!(backend.shown().len(), 1);
+
+        tokio::time::sleep(Duration::from_millis(2_000)).await;
+        tokio::task::yield_now().await;
+
+        let shown = backend.shown();
+        assert_eq!(shown.len(), 2);
+        backend.click(&manager, &shown[1].id, ClickAction::Body);
+
+        assert_eq!(
+            events(&emitted),
+            vec![ClickEvent::Digest(NotificationDigestEvent {
+                id: shown[1].id.clone(),
+                urls: vec!["cushion://chat/1".to_string(), "cushion://chat/3".to_string()],
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_held_notifications_are_in_history() {
+        let backend = recording_backend();
+        let config = ManagerConfig {
+            policy: QuietHoursPolicy {
+                paused_until: Some(u64::MAX),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let manager = NotificationManager::new(backend.clone(), config, tokio::runtime::Handle::current());
+
+        let mut quiet = request("cushion-1", None);
+        quiet.priority = NotificationPriority::Normal;
+        manager.show_notification(quiet).unwrap();
+
+        assert!(backend.shown().is_empty());
+        let history = manager.notification_history("", 10, 0);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].id, "cushion-1");
+    }
+
+    #[tokio::test]
+    async fn test_history_is_saved_in_the_background() {
+        let path = std::env::temp_dir().join(format!("cushion-history-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let backend = recording_backend();
+        let config = ManagerConfig {
+            history_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let manager = NotificationManager::new(backend.clone(), config, tokio::runtime::Handle::current());
+
+        manager.show_notification(request("cushion-1", None)).unwrap();
+        manager.show_notification(request("cushion-2", None)).unwrap();
+        assert!(!path.exists());
+
+        manager.flush_history();
+        let saved = NotificationHistory::load(&path, HistoryRetention::default());
+        assert_eq!(saved.search("", 10, 0).len(), 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_dismissals_reach_backend() {
+        let backend = recording_backend();
+        let (manager, _) = manager(&backend);
+
+        let mut threaded = request("cushion-1", None);
+        threaded.thread_id = Some("chat-1".to_string());
+        manager.show_notification(threaded).unwrap();
+
+        manager.clear_notifications_for_thread("chat-1").unwrap();
+        manager.dismiss_notification("cushion-2").unwrap();
+        manager.dismiss_all_notifications().unwrap();
+
+        assert_eq!(
+            backend.calls()[1..],
+            [
+                BackendCall::ClearThread("chat-1".to_string()),
+                BackendCall::Dismiss("cushion-2".to_string()),
+                BackendCall::DismissAll,
+            ]
+        );
+        assert!(manager.list_active_notifications().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_come_from_backend() {
+        let backend = recording_backend();
+        let (manager, _) = manager(&backend);
+
+        assert!(manager.capabilities().actions);
+        assert!(!manager.capabilities().body_markup);
+    }
+
+    #[tokio::test]
+    async fn test_failed_probe_still_sends_actions() {
+        let backend = Arc::new(RecordingBackend::without_capabilities("No notification server"));
+        let (manager, _) = manager(&backend);
+
+        assert_eq!(manager.capabilities(), NotificationCapabilities::assumed());
+        assert!(manager.capabilities().actions);
+    }
+}
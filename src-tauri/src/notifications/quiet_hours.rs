@@ -0,0 +1,386 @@
+//! Quiet hours (Do Not Disturb) policy
+//!
+//! Decides whether a notification is shown right away or held until the
+//! quiet period is over. Held notifications are delivered together as a
+//! single digest notification. The current time comes from a [`Clock`] so the
+//! scheduling logic can be tested without waiting for real time to pass.
+
+use super::{NotificationPriority, NotificationRequest};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// File in the app config directory holding the quiet hours policy
+pub const POLICY_FILE: &str = "quiet-hours.json";
+
+/// Maximum number of notifications held for the digest
+const MAX_HELD: usize = 100;
+
+/// Source of the current local time
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<FixedOffset>;
+}
+
+/// Clock reading the system's local time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<FixedOffset> {
+        Local::now().fixed_offset()
+    }
+}
+
+/// A weekly quiet window, e.g. weeknights from 22:00 to 08:00
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietWindow {
+    /// Days the window starts on
+    pub days: Vec<Weekday>,
+    /// Local time the window starts at
+    pub start: NaiveTime,
+    /// Local time the window ends at; before `start` for overnight windows
+    pub end: NaiveTime,
+}
+
+impl QuietWindow {
+    fn contains(&self, now: DateTime<FixedOffset>) -> bool {
+        let time = now.time();
+        let today = now.weekday();
+
+        if self.start < self.end {
+            self.days.contains(&today) && time >= self.start && time < self.end
+        } else {
+            // Overnight windows continue into the next day until `end`
+            (self.days.contains(&today) && time >= self.start)
+                || (self.days.contains(&today.pred()) && time < self.end)
+        }
+    }
+}
+
+/// User-configured quiet hours
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuietHoursPolicy {
+    /// Whether the weekly windows are applied
+    pub enabled: bool,
+    /// Weekly quiet windows
+    pub windows: Vec<QuietWindow>,
+    /// Manual pause end, in milliseconds since the Unix epoch
+    pub paused_until: Option<u64>,
+    /// Thread ids that are always delivered, e.g. urgent conversations
+    pub allowlist: Vec<String>,
+    /// Lowest priority delivered during quiet hours; `None` holds everything
+    pub breakthrough_priority: Option<NotificationPriority>,
+}
+
+impl Default for QuietHoursPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            windows: Vec::new(),
+            paused_until: None,
+            allowlist: Vec::new(),
+            breakthrough_priority: Some(NotificationPriority::High),
+        }
+    }
+}
+
+/// Quiet hours state: the policy plus the notifications held back by it
+pub struct QuietHours {
+    policy: QuietHoursPolicy,
+    held: Vec<NotificationRequest>,
+    clock: Arc<dyn Clock>,
+}
+
+impl QuietHours {
+    pub fn new(policy: QuietHoursPolicy, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            policy,
+            held: Vec::new(),
+            clock,
+        }
+    }
+
+    /// The current policy
+    pub fn policy(&self) -> &QuietHoursPolicy {
+        &self.policy
+    }
+
+    /// Replace the policy
+    pub fn set_policy(&mut self, policy: QuietHoursPolicy) {
+        self.policy = policy;
+    }
+
+    /// Pause notifications for the given duration
+    pub fn pause(&mut self, duration: Duration) {
+        let now = self.now_millis();
+        self.policy.paused_until = Some(now.saturating_add(duration.as_millis() as u64));
+    }
+
+    /// End a manual pause
+    pub fn resume(&mut self) {
+        self.policy.paused_until = None;
+    }
+
+    /// Whether notifications are currently held back
+    pub fn is_quiet(&self) -> bool {
+        if self
+            .policy
+            .paused_until
+            .is_some_and(|until| self.now_millis() < until)
+        {
+            return true;
+        }
+
+        let now = self.clock.now();
+        self.policy.enabled && self.policy.windows.iter().any(|window| window.contains(now))
+    }
+
+    /// Hold a notification during quiet hours
+    ///
+    /// Returns the notification back if it should be shown now.
+    pub fn filter(&mut self, request: NotificationRequest) -> Option<NotificationRequest> {
+        let allowed = request
+            .thread_id
+            .as_ref()
+            .is_some_and(|thread_id| self.policy.allowlist.contains(thread_id))
+            || self
+                .policy
+                .breakthrough_priority
+                .is_some_and(|priority| request.priority >= priority);
+
+        if allowed || !self.is_quiet() {
+            return Some(request);
+        }
+
+        if self.held.len() >= MAX_HELD {
+            self.held.remove(0);
+        }
+        self.held.push(request);
+        None
+    }
+
+    /// Take the held notifications once the quiet period is over
+    pub fn take_held(&mut self) -> Option<Vec<NotificationRequest>> {
+        if self.held.is_empty() || self.is_quiet() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.held))
+    }
+
+    fn now_millis(&self) -> u64 {
+        self.clock.now().timestamp_millis().max(0) as u64
+    }
+}
+
+/// Combine the notifications held during quiet hours into one digest
+///
+/// A single held notification is shown as is.
+pub fn digest(mut held: Vec<NotificationRequest>) -> Option<NotificationRequest> {
+    if held.len() <= 1 {
+        return held.pop();
+    }
+
+    let title = format!("{} notifications during quiet hours", held.len());
+    Some(super::batching::digest(held, title))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    struct FixedClock(Mutex<DateTime<FixedOffset>>);
+
+    impl FixedClock {
+        fn set(&self, time: DateTime<FixedOffset>) {
+            *self.0.lock().unwrap() = time;
+        }
+    }
+
+    impl Clock for FixedClock {
+        fn now(&self) -> DateTime<FixedOffset> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// 2024-01-01 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<FixedOffset> {
+        FixedOffset::east_opt(0)
+            .unwrap()
+            .with_ymd_and_hms(2024, 1, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn request(title: &str, thread_id: Option<&str>) -> NotificationRequest {
+        NotificationRequest {
+            id: format!("cushion-{}", title),
+            title: title.to_string(),
+            url: Some("cushion://inbox".to_string()),
+            thread_id: thread_id.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn weeknights() -> QuietHoursPolicy {
+        QuietHoursPolicy {
+            enabled: true,
+            windows: vec![QuietWindow {
+                days: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+                start: time(22, 0),
+                end: time(8, 0),
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn quiet_hours(policy: QuietHoursPolicy, now: DateTime<FixedOffset>) -> (QuietHours, Arc<FixedClock>) {
+        let clock = Arc::new(FixedClock(Mutex::new(now)));
+        (QuietHours::new(policy, clock.clone()), clock)
+    }
+
+    #[test]
+    fn test_overnight_window_spans_midnight() {
+        let (quiet, clock) = quiet_hours(weeknights(), at(1, 21, 59));
+        assert!(!quiet.is_quiet());
+
+        clock.set(at(1, 22, 0));
+        assert!(quiet.is_quiet());
+
+        // Tuesday morning is still inside Monday night's window
+        clock.set(at(2, 7, 59));
+        assert!(quiet.is_quiet());
+        clock.set(at(2, 8, 0));
+        assert!(!quiet.is_quiet());
+
+        // Friday night runs into Saturday morning, but Saturday night is free
+        clock.set(at(6, 7, 0));
+        assert!(quiet.is_quiet());
+        clock.set(at(6, 23, 0));
+        assert!(!quiet.is_quiet());
+        clock.set(at(7, 7, 0));
+        assert!(!quiet.is_quiet());
+    }
+
+    #[test]
+    fn test_disabled_policy_ignores_windows() {
+        let policy = QuietHoursPolicy {
+            enabled: false,
+            ..weeknights()
+        };
+        let (quiet, _clock) = quiet_hours(policy, at(1, 23, 0));
+        assert!(!quiet.is_quiet());
+    }
+
+    #[test]
+    fn test_pause_expires() {
+        let (mut quiet, clock) = quiet_hours(QuietHoursPolicy::default(), at(3, 12, 0));
+        quiet.pause(Duration::from_secs(30 * 60));
+        assert!(quiet.is_quiet());
+
+        clock.set(at(3, 12, 29));
+        assert!(quiet.is_quiet());
+        clock.set(at(3, 12, 30));
+        assert!(!quiet.is_quiet());
+
+        quiet.pause(Duration::from_secs(60 * 60));
+        quiet.resume();
+        assert!(!quiet.is_quiet());
+    }
+
+    #[test]
+    fn test_allowlisted_threads_bypass_quiet_hours() {
+        let policy = QuietHoursPolicy {
+            allowlist: vec!["urgent".to_string()],
+            ..weeknights()
+        };
+        let (mut quiet, _clock) = quiet_hours(policy, at(1, 23, 0));
+
+        assert!(quiet.filter(request("urgent", Some("urgent"))).is_some());
+        assert!(quiet.filter(request("other", Some("other"))).is_none());
+        assert!(quiet.filter(request("none", None)).is_none());
+    }
+
+    #[test]
+    fn test_high_priority_breaks_through() {
+        let (mut quiet, _clock) = quiet_hours(weeknights(), at(1, 23, 0));
+
+        let mut mention = request("mention", None);
+        mention.priority = NotificationPriority::High;
+        let mut fyi = request("fyi", None);
+        fyi.priority = NotificationPriority::Low;
+        assert!(quiet.filter(mention.clone()).is_some());
+        assert!(quiet.filter(fyi).is_none());
+
+        // Only critical notifications get through a stricter policy
+        quiet.set_policy(QuietHoursPolicy {
+            breakthrough_priority: Some(NotificationPriority::Critical),
+            ..weeknights()
+        });
+        assert!(quiet.filter(mention.clone()).is_none());
+
+        quiet.set_policy(QuietHoursPolicy {
+            breakthrough_priority: None,
+            ..weeknights()
+        });
+        mention.priority = NotificationPriority::Critical;
+        assert!(quiet.filter(mention).is_none());
+    }
+
+    #[test]
+    fn test_held_notifications_released_when_window_ends() {
+        let (mut quiet, clock) = quiet_hours(weeknights(), at(1, 23, 0));
+        assert!(quiet.filter(request("a", None)).is_none());
+        assert!(quiet.filter(request("b", None)).is_none());
+        assert!(quiet.take_held().is_none());
+
+        clock.set(at(2, 8, 0));
+        let titles: Vec<String> = quiet
+            .take_held()
+            .unwrap()
+            .into_iter()
+            .map(|request| request.title)
+            .collect();
+        assert_eq!(titles, vec!["a".to_string(), "b".to_string()]);
+        assert!(quiet.take_held().is_none());
+    }
+
+    #[test]
+    fn test_digest_summarizes_held_notifications() {
+        assert!(digest(Vec::new()).is_none());
+        assert_eq!(digest(vec![request("only", None)]).unwrap().id, "cushion-only");
+
+        let held: Vec<NotificationRequest> = (1..=7)
+            .map(|n| request(&format!("message {}", n), None))
+            .collect();
+        let summary = digest(held).unwrap();
+        assert_eq!(summary.title, "7 notifications during quiet hours");
+        assert!(summary.body.ends_with("message 5\nand 2 more"));
+        assert_eq!(summary.url.as_deref(), Some("cushion://inbox"));
+
+        let mut other = request("other", None);
+        other.url = Some("cushion://elsewhere".to_string());
+        assert!(digest(vec![request("a", None), other]).unwrap().url.is_none());
+    }
+
+    #[test]
+    fn test_policy_round_trips_through_json() {
+        let json = r#"{"enabled":true,"windows":[{"days":["Sat","Sun"],"start":"00:00","end":"00:00"}]}"#;
+        let policy: QuietHoursPolicy = serde_json::from_str(json).unwrap();
+        assert_eq!(policy.windows[0].days, vec![Weekday::Sat, Weekday::Sun]);
+        assert!(policy.allowlist.is_empty());
+
+        // A window starting and ending at the same time covers the whole day
+        let (quiet, _clock) = quiet_hours(policy.clone(), at(6, 13, 0));
+        assert!(quiet.is_quiet());
+
+        let saved = serde_json::to_string(&policy).unwrap();
+        assert_eq!(serde_json::from_str::<QuietHoursPolicy>(&saved).unwrap(), policy);
+    }
+}
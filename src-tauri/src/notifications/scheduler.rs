@@ -0,0 +1,324 @@
+//! Scheduled notifications
+//!
+//! Notifications scheduled for later (reminders, snoozed notifications) are
+//! kept here and persisted to the app data directory so they survive reloads
+//! and restarts. Due times are wall-clock times: timers never sleep longer
+//! than [`MAX_SLEEP`] before checking the clock again, so notifications that
+//! came due while the computer was asleep are shown soon after it wakes up.
+
+use super::quiet_hours::Clock;
+use super::NotificationRequest;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// File in the app data directory holding the scheduled notifications
+pub const SCHEDULE_FILE: &str = "scheduled-notifications.json";
+
+/// Most notifications that can be scheduled at once, since each one is kept
+/// in memory and written out with every change to the schedule
+pub const MAX_SCHEDULED: usize = 256;
+
+/// Longest time between two checks of the wall clock
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// A notification waiting to be shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledNotification {
+    /// When to show the notification, in milliseconds since the Unix epoch
+    pub at: u64,
+    pub request: NotificationRequest,
+}
+
+/// Pending scheduled notifications and the timer loop delivering them
+pub struct Scheduler {
+    /// Pending notifications, soonest first
+    pending: Mutex<Vec<ScheduledNotification>>,
+    /// Wakes the timer loop when the schedule changes
+    changed: Notify,
+    clock: Arc<dyn Clock>,
+    /// Where the schedule is saved, if anywhere
+    path: Option<PathBuf>,
+}
+
+impl Scheduler {
+    pub fn new(mut pending: Vec<ScheduledNotification>, clock: Arc<dyn Clock>, path: Option<PathBuf>) -> Self {
+        pending.sort_by_key(|scheduled| scheduled.at);
+        Self {
+            pending: Mutex::new(pending),
+            changed: Notify::new(),
+            clock,
+            path,
+        }
+    }
+
+    /// Load the saved schedule, starting empty if there is none
+    pub fn load(path: Option<PathBuf>, clock: Arc<dyn Clock>) -> Self {
+        let pending = path
+            .as_deref()
+            .and_then(crate::storage::read_json)
+            .unwrap_or_default();
+        Self::new(pending, clock, path)
+    }
+
+    /// Schedule a notification, replacing any pending one with the same id
+    ///
+    /// Fails if [`MAX_SCHEDULED`] other notifications are already pending.
+    pub fn schedule(&self, scheduled: ScheduledNotification) -> Result<(), String> {
+        let mut pending = self.pending.lock().unwrap();
+        let replaces = pending
+            .iter()
+            .any(|other| other.request.id == scheduled.request.id);
+        if !replaces && pending.len() >= MAX_SCHEDULED {
+            return Err(format!(
+                "Can't schedule more than {} notifications",
+                MAX_SCHEDULED
+            ));
+        }
+        pending.retain(|other| other.request.id != scheduled.request.id);
+        let index = pending.partition_point(|other| other.at <= scheduled.at);
+        pending.insert(index, scheduled);
+        self.save(&pending)?;
+        drop(pending);
+
+        self.changed.notify_one();
+        Ok(())
+    }
+
+    /// Cancel a pending notification, returning whether it was found
+    pub fn cancel(&self, id: &str) -> Result<bool, String> {
+        let mut pending = self.pending.lock().unwrap();
+        let count = pending.len();
+        pending.retain(|scheduled| scheduled.request.id != id);
+        if pending.len() == count {
+            return Ok(false);
+        }
+        self.save(&pending)?;
+        Ok(true)
+    }
+
+    /// List the pending notifications, soonest first
+    pub fn list(&self) -> Vec<ScheduledNotification> {
+        self.pending.lock().unwrap().clone()
+    }
+
+    /// Deliver notifications as they come due; never returns
+    pub async fn run<F>(&self, deliver: F)
+    where
+        F: Fn(NotificationRequest),
+    {
+        loop {
+            for request in self.take_due() {
+                deliver(request);
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(self.time_until_next()) => {}
+                _ = self.changed.notified() => {}
+            }
+        }
+    }
+
+    /// Remove and return the notifications that are due
+    fn take_due(&self) -> Vec<NotificationRequest> {
+        let now = self.now_millis();
+        let mut pending = self.pending.lock().unwrap();
+
+        let due_count = pending.partition_point(|scheduled| scheduled.at <= now);
+        if due_count == 0 {
+            return Vec::new();
+        }
+        let due: Vec<NotificationRequest> = pending
+            .drain(..due_count)
+            .map(|scheduled| scheduled.request)
+            .collect();
+
+        if let Err(e) = self.save(&pending) {
+            eprintln!("❌ Failed to save scheduled notifications: {}", e);
+        }
+        due
+    }
+
+    /// How long to sleep before checking for due notifications again
+    fn time_until_next(&self) -> Duration {
+        let now = self.now_millis();
+        match self.pending.lock().unwrap().first() {
+            Some(next) => Duration::from_millis(next.at.saturating_sub(now)).min(MAX_SLEEP),
+            None => MAX_SLEEP,
+        }
+    }
+
+    fn save(&self, pending: &[ScheduledNotification]) -> Result<(), String> {
+        match self.path {
+            Some(ref path) => crate::storage::write_json(path, &pending),
+            None => Ok(()),
+        }
+    }
+
+    fn now_millis(&self) -> u64 {
+        self.clock.now().timestamp_millis().max(0) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, FixedOffset};
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    /// Wall clock that only moves when told to, independently of tokio's timers
+    struct TestClock(AtomicI64);
+
+    impl TestClock {
+        fn set(&self, millis: i64) {
+            self.0.store(millis, Ordering::SeqCst);
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> DateTime<FixedOffset> {
+            DateTime::from_timestamp_millis(self.0.load(Ordering::SeqCst))
+                .unwrap()
+                .fixed_offset()
+        }
+    }
+
+    fn scheduled(id: &str, at: u64) -> ScheduledNotification {
+        ScheduledNotification {
+            at,
+            request: NotificationRequest {
+                id: id.to_string(),
+                title: id.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn ids(scheduler: &Scheduler) -> Vec<String> {
+        scheduler.list().into_iter().map(|s| s.request.id).collect()
+    }
+
+    fn scheduler(now: i64) -> (Arc<Scheduler>, Arc<TestClock>) {
+        let clock = Arc::new(TestClock(AtomicI64::new(now)));
+        (Arc::new(Scheduler::new(Vec::new(), clock.clone(), None)), clock)
+    }
+
+    /// Run the timer loop in the background, collecting delivered ids
+    fn run(scheduler: &Arc<Scheduler>) -> Arc<Mutex<Vec<String>>> {
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let sink = delivered.clone();
+        let scheduler = scheduler.clone();
+        tokio::spawn(async move {
+            scheduler
+                .run(move |request| sink.lock().unwrap().push(request.id))
+                .await;
+        });
+        delivered
+    }
+
+    async fn advance(duration: Duration) {
+        tokio::time::sleep(duration).await;
+        tokio::task::yield_now().await;
+    }
+
+    #[test]
+    fn test_schedule_orders_replaces_and_cancels() {
+        let (scheduler, _clock) = scheduler(0);
+        scheduler.schedule(scheduled("b", 2_000)).unwrap();
+        scheduler.schedule(scheduled("a", 1_000)).unwrap();
+        scheduler.schedule(scheduled("c", 3_000)).unwrap();
+        assert_eq!(ids(&scheduler), vec!["a", "b", "c"]);
+
+        // Rescheduling the same id moves it
+        scheduler.schedule(scheduled("a", 4_000)).unwrap();
+        assert_eq!(ids(&scheduler), vec!["b", "c", "a"]);
+
+        assert!(scheduler.cancel("c").unwrap());
+        assert!(!scheduler.cancel("c").unwrap());
+        assert_eq!(ids(&scheduler), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn test_schedule_is_capped() {
+        let (scheduler, _clock) = scheduler(0);
+        for n in 0..MAX_SCHEDULED {
+            scheduler.schedule(scheduled(&n.to_string(), 1_000)).unwrap();
+        }
+
+        assert!(scheduler.schedule(scheduled("one-more", 1_000)).is_err());
+        // Rescheduling a pending notification still works
+        scheduler.schedule(scheduled("0", 2_000)).unwrap();
+        assert_eq!(scheduler.list().len(), MAX_SCHEDULED);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delivers_when_due() {
+        let (scheduler, clock) = scheduler(0);
+        scheduler.schedule(scheduled("a", 5_000)).unwrap();
+        let delivered = run(&scheduler);
+        advance(Duration::ZERO).await;
+
+        clock.set(4_999);
+        advance(Duration::from_millis(4_999)).await;
+        assert!(delivered.lock().unwrap().is_empty());
+
+        clock.set(5_000);
+        advance(Duration::from_millis(1)).await;
+        assert_eq!(*delivered.lock().unwrap(), vec!["a"]);
+        assert!(scheduler.list().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wakes_up_for_newly_scheduled() {
+        let (scheduler, clock) = scheduler(0);
+        let delivered = run(&scheduler);
+        advance(Duration::ZERO).await;
+
+        // The loop is sleeping with nothing pending; an earlier entry wakes it
+        scheduler.schedule(scheduled("a", 0)).unwrap();
+        advance(Duration::ZERO).await;
+        assert_eq!(*delivered.lock().unwrap(), vec!["a"]);
+
+        clock.set(10);
+        scheduler.schedule(scheduled("b", 1_000)).unwrap();
+        clock.set(1_010);
+        advance(Duration::from_millis(1_000)).await;
+        assert_eq!(*delivered.lock().unwrap(), vec!["a", "b"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_catches_up_after_wall_clock_jump() {
+        let hour = 60 * 60 * 1000;
+        let (scheduler, clock) = scheduler(0);
+        scheduler.schedule(scheduled("a", hour)).unwrap();
+        let delivered = run(&scheduler);
+        advance(Duration::ZERO).await;
+
+        // The system sleeps for an hour: the wall clock jumps, the timers don't
+        clock.set(hour as i64 + 1);
+        advance(Duration::from_secs(1)).await;
+        assert!(delivered.lock().unwrap().is_empty());
+
+        // The next wall-clock check picks it up
+        advance(MAX_SLEEP).await;
+        assert_eq!(*delivered.lock().unwrap(), vec!["a"]);
+    }
+
+    #[test]
+    fn test_schedule_survives_restart() {
+        let path = std::env::temp_dir().join(format!("cushion-schedule-{}.json", uuid::Uuid::new_v4()));
+        let clock = Arc::new(TestClock(AtomicI64::new(0)));
+
+        let scheduler = Scheduler::load(Some(path.clone()), clock.clone());
+        scheduler.schedule(scheduled("b", 2_000)).unwrap();
+        scheduler.schedule(scheduled("a", 1_000)).unwrap();
+
+        let restarted = Scheduler::load(Some(path.clone()), clock);
+        assert_eq!(ids(&restarted), vec!["a", "b"]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
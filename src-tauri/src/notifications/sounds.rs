@@ -0,0 +1,178 @@
+//! Notification sounds
+//!
+//! A notification plays the platform default sound, no sound, or one of the
+//! sounds bundled with the app, by name. Each category of notification (mentions, replies,
+//! digests) has a user-configurable sound, used unless the webview picks one
+//! for a specific notification.
+
+use serde::{Deserialize, Serialize};
+
+/// File in the app config directory holding the sound profiles
+pub const PROFILES_FILE: &str = "notification-sounds.json";
+
+/// Sounds available by name
+///
+/// Each is bundled as [`bundled_file`] at the root of the app's resources
+/// (see `bundle.resources` in `tauri.conf.json`), so a name sounds the same
+/// on every platform.
+pub const NAMED_SOUNDS: &[&str] = &["ping", "mail", "reminder"];
+
+/// File name of a named sound in the app's resource directory
+pub fn bundled_file(name: &str) -> String {
+    format!("cushion-{}.wav", name)
+}
+
+/// Sound played when a notification is shown
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationSound {
+    /// The platform's default notification sound
+    #[default]
+    Default,
+    /// No sound
+    None,
+    /// One of [`NAMED_SOUNDS`], by name
+    Named(String),
+}
+
+impl NotificationSound {
+    /// Check that a named sound exists
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Named(name) if !NAMED_SOUNDS.contains(&name.as_str()) => {
+                Err(format!("Unknown notification sound '{}'", name))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Kind of notification, used to pick its sound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationCategory {
+    Mention,
+    Reply,
+    Digest,
+}
+
+/// User-configured sound for each notification category
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SoundProfiles {
+    pub mentions: NotificationSound,
+    pub replies: NotificationSound,
+    pub digests: NotificationSound,
+}
+
+impl Default for SoundProfiles {
+    fn default() -> Self {
+        Self {
+            mentions: NotificationSound::Default,
+            replies: NotificationSound::Default,
+            // Digests summarize notifications that already had their chance
+            digests: NotificationSound::None,
+        }
+    }
+}
+
+impl SoundProfiles {
+    /// Check that every named sound exists
+    pub fn validate(&self) -> Result<(), String> {
+        self.mentions.validate()?;
+        self.replies.validate()?;
+        self.digests.validate()
+    }
+
+    /// Pick the sound for a notification: an explicit choice wins, then the
+    /// category's profile, then the platform default
+    pub fn resolve(
+        &self,
+        sound: Option<&NotificationSound>,
+        category: Option<NotificationCategory>,
+    ) -> NotificationSound {
+        if let Some(sound) = sound {
+            return sound.clone();
+        }
+
+        match category {
+            Some(NotificationCategory::Mention) => self.mentions.clone(),
+            Some(NotificationCategory::Reply) => self.replies.clone(),
+            Some(NotificationCategory::Digest) => self.digests.clone(),
+            None => NotificationSound::Default,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_named_sounds_are_bundled() {
+        let config: serde_json::Value =
+            serde_json::from_str(include_str!("../../tauri.conf.json")).unwrap();
+        let resources = &config["bundle"]["resources"];
+        let sounds_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("sounds");
+
+        for name in NAMED_SOUNDS {
+            let file = bundled_file(name);
+            assert!(sounds_dir.join(&file).is_file(), "{} is missing", file);
+            assert_eq!(
+                resources[format!("sounds/{}", file)].as_str(),
+                Some(file.as_str()),
+                "{} isn't bundled",
+                file
+            );
+        }
+    }
+
+    #[test]
+    fn test_sound_json_forms() {
+        let sounds: Vec<NotificationSound> =
+            serde_json::from_str(r#"["default", "none", {"named": "ping"}]"#).unwrap();
+        assert_eq!(
+            sounds,
+            vec![
+                NotificationSound::Default,
+                NotificationSound::None,
+                NotificationSound::Named("ping".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_names() {
+        assert!(NotificationSound::Named("ping".to_string()).validate().is_ok());
+        assert!(NotificationSound::Named("../ping".to_string()).validate().is_err());
+
+        let profiles = SoundProfiles {
+            replies: NotificationSound::Named("missing".to_string()),
+            ..Default::default()
+        };
+        assert!(profiles.validate().is_err());
+    }
+
+    #[test]
+    fn test_resolve_prefers_explicit_sound_then_category() {
+        let profiles = SoundProfiles {
+            mentions: NotificationSound::Named("ping".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            profiles.resolve(Some(&NotificationSound::None), Some(NotificationCategory::Mention)),
+            NotificationSound::None
+        );
+        assert_eq!(
+            profiles.resolve(None, Some(NotificationCategory::Mention)),
+            NotificationSound::Named("ping".to_string())
+        );
+        assert_eq!(
+            profiles.resolve(None, Some(NotificationCategory::Digest)),
+            NotificationSound::None
+        );
+        assert_eq!(profiles.resolve(None, None), NotificationSound::Default);
+    }
+}
@@ -0,0 +1,277 @@
+//! Bounded notification metadata store
+//!
+//! Keeps what we need to route clicks back to the webview (URL, thread key,
+//! payload) for each shown notification. Entries are evicted least recently
+//! used first once the store is full, and expire after a fixed TTL.
+
+use super::NotificationRequest;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Metadata kept for a shown notification
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationMetadata {
+    /// Deep link URL opened when the notification body is clicked
+    pub url: Option<String>,
+    /// When the notification was shown, in milliseconds since the Unix epoch
+    pub created_at: u64,
+    /// Thread/group key the notification belongs to
+    pub thread_key: Option<String>,
+    /// Arbitrary JSON payload supplied by the webview
+    pub payload: Option<serde_json::Value>,
+    /// URLs of the notifications summarized by a digest notification
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub digest_urls: Vec<String>,
+    /// The original request of a snoozable notification
+    #[serde(skip)]
+    pub snooze_request: Option<NotificationRequest>,
+}
+
+/// A notification that is still tracked by the store
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveNotification {
+    pub id: String,
+    #[serde(flatten)]
+    pub metadata: NotificationMetadata,
+}
+
+struct Entry {
+    metadata: NotificationMetadata,
+    /// Value of the access counter when the entry was last used
+    last_used: u64,
+}
+
+/// Notification metadata with LRU and TTL eviction
+pub struct MetadataStore {
+    entries: HashMap<String, Entry>,
+    capacity: usize,
+    ttl: Duration,
+    /// Monotonic counter used to order entries by recency
+    access_counter: u64,
+}
+
+impl MetadataStore {
+    /// Create a store holding at most `capacity` entries for up to `ttl`
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            ttl,
+            access_counter: 0,
+        }
+    }
+
+    /// Insert (or replace) the metadata for a notification
+    pub fn insert(&mut self, id: String, metadata: NotificationMetadata, now: SystemTime) {
+        self.remove_expired(now);
+
+        let last_used = self.next_access();
+        self.entries.insert(id, Entry { metadata, last_used });
+
+        while self.entries.len() > self.capacity {
+            self.evict_least_recently_used();
+        }
+    }
+
+    /// Look up the metadata for a notification, marking it as recently used
+    pub fn get(&mut self, id: &str, now: SystemTime) -> Option<NotificationMetadata> {
+        if self.entries.get(id).is_some_and(|entry| self.is_expired(entry, now)) {
+            self.entries.remove(id);
+            return None;
+        }
+
+        let last_used = self.next_access();
+        let entry = self.entries.get_mut(id)?;
+        entry.last_used = last_used;
+        Some(entry.metadata.clone())
+    }
+
+    /// Remove the metadata for a notification once it has been handled
+    pub fn remove(&mut self, id: &str) -> Option<NotificationMetadata> {
+        self.entries.remove(id).map(|entry| entry.metadata)
+    }
+
+    /// Remove every notification of a thread, returning their ids
+    pub fn remove_thread(&mut self, thread_key: &str) -> Vec<String> {
+        let ids: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.metadata.thread_key.as_deref() == Some(thread_key))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &ids {
+            self.entries.remove(id);
+        }
+        ids
+    }
+
+    /// Remove the metadata of every notification
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// List the notifications that haven't expired, oldest first
+    pub fn active(&mut self, now: SystemTime) -> Vec<ActiveNotification> {
+        self.remove_expired(now);
+
+        let mut active: Vec<ActiveNotification> = self
+            .entries
+            .iter()
+            .map(|(id, entry)| ActiveNotification {
+                id: id.clone(),
+                metadata: entry.metadata.clone(),
+            })
+            .collect();
+        active.sort_by(|a, b| {
+            a.metadata
+                .created_at
+                .cmp(&b.metadata.created_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        active
+    }
+
+    fn next_access(&mut self) -> u64 {
+        self.access_counter += 1;
+        self.access_counter
+    }
+
+    fn is_expired(&self, entry: &Entry, now: SystemTime) -> bool {
+        let ttl_ms = self.ttl.as_millis() as u64;
+        unix_millis(now).saturating_sub(entry.metadata.created_at) >= ttl_ms
+    }
+
+    fn remove_expired(&mut self, now: SystemTime) {
+        let expired: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| self.is_expired(entry, now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired {
+            self.entries.remove(&id);
+        }
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(id, _)| id.clone());
+        if let Some(id) = oldest {
+            self.entries.remove(&id);
+        }
+    }
+}
+
+/// Milliseconds since the Unix epoch
+pub fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn metadata(url: &str, created: SystemTime) -> NotificationMetadata {
+        NotificationMetadata {
+            url: Some(url.to_string()),
+            created_at: unix_millis(created),
+            thread_key: None,
+            payload: None,
+            digest_urls: Vec::new(),
+            snooze_request: None,
+        }
+    }
+
+    fn thread_metadata(thread_key: &str, created: SystemTime) -> NotificationMetadata {
+        NotificationMetadata {
+            thread_key: Some(thread_key.to_string()),
+            ..metadata("cushion://thread", created)
+        }
+    }
+
+    #[test]
+    fn test_lookup_and_remove() {
+        let mut store = MetadataStore::new(10, Duration::from_secs(60));
+        store.insert("a".to_string(), metadata("cushion://a", at(0)), at(0));
+
+        assert_eq!(
+            store.get("a", at(1)).and_then(|m| m.url),
+            Some("cushion://a".to_string())
+        );
+        assert!(store.get("missing", at(1)).is_none());
+
+        assert!(store.remove("a").is_some());
+        assert!(store.get("a", at(1)).is_none());
+        assert_eq!(store.entries.len(), 0);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_when_full() {
+        let mut store = MetadataStore::new(2, Duration::from_secs(60));
+        store.insert("a".to_string(), metadata("cushion://a", at(0)), at(0));
+        store.insert("b".to_string(), metadata("cushion://b", at(1)), at(1));
+
+        // Touch "a" so "b" becomes the least recently used
+        assert!(store.get("a", at(2)).is_some());
+        store.insert("c".to_string(), metadata("cushion://c", at(3)), at(3));
+
+        assert_eq!(store.entries.len(), 2);
+        assert!(store.get("a", at(4)).is_some());
+        assert!(store.get("b", at(4)).is_none());
+        assert!(store.get("c", at(4)).is_some());
+    }
+
+    #[test]
+    fn test_entries_expire_after_ttl() {
+        let mut store = MetadataStore::new(10, Duration::from_secs(60));
+        store.insert("old".to_string(), metadata("cushion://old", at(0)), at(0));
+        store.insert("new".to_string(), metadata("cushion://new", at(30)), at(30));
+
+        assert!(store.get("old", at(59)).is_some());
+        assert!(store.get("old", at(60)).is_none());
+
+        // Inserting purges anything that has expired in the meantime
+        store.insert("newest".to_string(), metadata("cushion://newest", at(95)), at(95));
+        assert_eq!(store.entries.len(), 1);
+        assert!(store.get("newest", at(95)).is_some());
+    }
+
+    #[test]
+    fn test_active_lists_unexpired_oldest_first() {
+        let mut store = MetadataStore::new(10, Duration::from_secs(60));
+        store.insert("b".to_string(), metadata("cushion://b", at(20)), at(20));
+        store.insert("a".to_string(), metadata("cushion://a", at(10)), at(20));
+        store.insert("expired".to_string(), metadata("cushion://x", at(0)), at(20));
+
+        let ids: Vec<String> = store.active(at(65)).into_iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_remove_thread_only_removes_that_thread() {
+        let mut store = MetadataStore::new(10, Duration::from_secs(60));
+        store.insert("a1".to_string(), thread_metadata("a", at(0)), at(0));
+        store.insert("a2".to_string(), thread_metadata("a", at(1)), at(1));
+        store.insert("b1".to_string(), thread_metadata("b", at(2)), at(2));
+        store.insert("none".to_string(), metadata("cushion://none", at(3)), at(3));
+
+        let mut removed = store.remove_thread("a");
+        removed.sort();
+        assert_eq!(removed, vec!["a1".to_string(), "a2".to_string()]);
+
+        let ids: Vec<String> = store.active(at(4)).into_iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec!["b1".to_string(), "none".to_string()]);
+    }
+}
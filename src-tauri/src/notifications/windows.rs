@@ -1,13 +1,50 @@
-/// Windows notification implementation using tauri-winrt-notification
+/// Windows notification implementation using WinRT toast notifications
 ///
-/// This provides native Windows toast notifications with click handling.
+/// Toasts are built from XML so they can carry action buttons and an inline
+/// reply input box, with click handling through the toast's events.
 
-use super::{ClickAction, NotificationClick, NotificationManager};
+use super::{
+    ClickAction, NotificationClick, NotificationManager, NotificationPermission,
+    NotificationRequest,
+};
 use std::sync::Arc;
 use tauri::AppHandle;
 
 #[cfg(target_os = "windows")]
-use tauri_winrt_notification::{Duration, Sound, Toast};
+use super::{
+    backend::NotificationBackend,
+    images::ImageKind,
+    markup::{render_body, BodyFormat},
+    sounds::bundled_file,
+    NotificationCapabilities, NotificationPriority, NotificationSound, PermissionStatus,
+    REPLY_ACTION_ID,
+};
+#[cfg(target_os = "windows")]
+use tauri::Manager;
+#[cfg(target_os = "windows")]
+use windows::{
+    core::{IInspectable, Interface, HSTRING},
+    Win32::Media::Audio::{PlaySoundW, SND_ASYNC, SND_FILENAME, SND_NODEFAULT},
+    Data::Xml::Dom::XmlDocument,
+    Foundation::{IPropertyValue, TypedEventHandler},
+    UI::Notifications::{
+        NotificationSetting, ToastActivatedEventArgs, ToastDismissalReason,
+        ToastDismissedEventArgs, ToastNotification, ToastNotificationManager,
+        ToastNotificationPriority,
+    },
+};
+
+/// Longest body shown, in graphemes; toasts show up to four lines of text
+#[cfg(target_os = "windows")]
+const MAX_BODY_LEN: usize = 300;
+
+/// Id of the toast input box holding the reply text
+#[cfg(target_os = "windows")]
+const REPLY_INPUT_ID: &str = "replyText";
+
+/// Toast audio source of the default notification sound
+#[cfg(target_os = "windows")]
+const DEFAULT_SOUND: &str = "ms-winsoundevent:Notification.Default";
 
 /// Setup Windows notification handling
 #[cfg(target_os = "windows")]
@@ -18,13 +55,7 @@ pub fn setup(_manager: Arc<NotificationManager>) {
 
 /// Show a notification on Windows
 #[cfg(target_os = "windows")]
-pub fn show_notification(
-    app: AppHandle,
-    id: String,
-    title: String,
-    body: String,
-    url: Option<String>,
-) -> Result<(), String> {
+pub fn show_notification(app: AppHandle, request: &NotificationRequest) -> Result<(), String> {
     // Get the app's bundle identifier for the toast
     let app_id = app.config()
         .identifier
@@ -34,45 +65,379 @@ pub fn show_notification(
     let manager = NotificationManager::get()
         .ok_or_else(|| "Notification manager not initialized".to_string())?;
 
-    let notification_id = id.clone();
-    let notification_url = url.clone();
+    show_toast(&app_id, request, manager)
+        .map_err(|e| format!("Failed to show Windows notification: {}", e))?;
 
-    // Create and show the toast
-    Toast::new(&app_id)
-        .title(&title)
-        .text1(&body)
-        .sound(Some(Sound::Default))
-        .duration(Duration::Short)
-        .on_activated(move |action| {
-            println!("🪟 Windows toast activated: {:?}", action);
+    // Toasts of unpackaged apps can't play the app's own sound files, so a
+    // named sound's toast is silent and the sound is played here
+    if let Some(NotificationSound::Named(ref name)) = request.sound {
+        play_bundled_sound(&app, name);
+    }
+    Ok(())
+}
 
-            let click = NotificationClick {
+/// Play one of the sounds bundled in the app's resource directory
+#[cfg(target_os = "windows")]
+fn play_bundled_sound(app: &AppHandle, name: &str) {
+    let Ok(dir) = app.path().resource_dir() else {
+        eprintln!("❌ Failed to find the notification sound {}", name);
+        return;
+    };
+    let path = HSTRING::from(dir.join(bundled_file(name)).as_path());
+    let played = unsafe { PlaySoundW(&path, None, SND_FILENAME | SND_ASYNC | SND_NODEFAULT) };
+    if !played.as_bool() {
+        eprintln!("❌ Failed to play the notification sound {}", name);
+    }
+}
+
+/// Build the toast from XML, wire up its events and show it
+#[cfg(target_os = "windows")]
+fn show_toast(
+    app_id: &str,
+    request: &NotificationRequest,
+    manager: Arc<NotificationManager>,
+) -> windows::core::Result<()> {
+    let xml = XmlDocument::new()?;
+    xml.LoadXml(&HSTRING::from(toast_xml(request)))?;
+    let toast = ToastNotification::CreateToastNotification(&xml)?;
+
+    let notification_id = request.id.clone();
+    let notification_url = request.url.clone();
+    let activated_manager = manager.clone();
+    toast.Activated(&TypedEventHandler::<ToastNotification, IInspectable>::new(move |_, args| {
+        let action = activated_action(&args);
+        println!("🪟 Windows toast activated: {:?}", action);
+
+        activated_manager.handle_click(NotificationClick {
+            id: notification_id.clone(),
+            url: notification_url.clone(),
+            action,
+        });
+        Ok(())
+    }))?;
+
+    let notification_id = request.id.clone();
+    let notification_url = request.url.clone();
+    toast.Dismissed(&TypedEventHandler::<ToastNotification, ToastDismissedEventArgs>::new(move |_, args| {
+        // Only report dismissals by the user, not timeouts or our own removals
+        if dismissal_reason(&args) == Some(ToastDismissalReason::UserCanceled) {
+            manager.handle_click(NotificationClick {
                 id: notification_id.clone(),
                 url: notification_url.clone(),
-                action: match action {
-                    None => ClickAction::Body,
-                    Some(btn) => ClickAction::Button(btn),
-                },
-            };
+                action: ClickAction::Dismiss,
+            });
+        }
+        Ok(())
+    }))?;
+
+    // Low priority goes straight to Action Center, high priority jumps the queue
+    match request.priority {
+        NotificationPriority::Low => toast.SetSuppressPopup(true)?,
+        NotificationPriority::Normal => {}
+        NotificationPriority::High | NotificationPriority::Critical => {
+            toast.SetPriority(ToastNotificationPriority::High)?
+        }
+    }
+
+    // Tag by notification id so the toast can be removed later. Toasts of the
+    // same thread share a group, and a new one replaces the previous ones.
+    toast.SetTag(&HSTRING::from(&request.id))?;
+    if let Some(ref thread_id) = request.thread_id {
+        let group = HSTRING::from(thread_id);
+        toast.SetGroup(&group)?;
+        if let Ok(history) = ToastNotificationManager::History() {
+            let _ = history.RemoveGroupWithId(&group, &HSTRING::from(app_id));
+        }
+    }
+
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(app_id))?.Show(&toast)
+}
 
-            manager.handle_click(click);
+/// Remove all toasts of a thread from Action Center
+#[cfg(target_os = "windows")]
+pub fn clear_thread(app: AppHandle, thread_id: &str) -> Result<(), String> {
+    let app_id = app.config()
+        .identifier
+        .clone();
+
+    ToastNotificationManager::History()
+        .and_then(|history| {
+            history.RemoveGroupWithId(&HSTRING::from(thread_id), &HSTRING::from(&app_id))
         })
-        .show()
-        .map_err(|e| format!("Failed to show Windows notification: {}", e))?;
+        .map_err(|e| format!("Failed to clear Windows notifications: {}", e))
+}
 
-    Ok(())
+/// Remove a single toast from Action Center
+#[cfg(target_os = "windows")]
+pub fn dismiss_notification(app: AppHandle, id: &str, thread_id: Option<&str>) -> Result<(), String> {
+    let app_id = app.config()
+        .identifier
+        .clone();
+
+    // Toasts without a thread have an empty group
+    ToastNotificationManager::History()
+        .and_then(|history| {
+            history.RemoveGroupedTagWithId(
+                &HSTRING::from(id),
+                &HSTRING::from(thread_id.unwrap_or_default()),
+                &HSTRING::from(&app_id),
+            )
+        })
+        .map_err(|e| format!("Failed to dismiss Windows notification: {}", e))
+}
+
+/// Remove all of our toasts from Action Center
+#[cfg(target_os = "windows")]
+pub fn dismiss_all_notifications(app: AppHandle) -> Result<(), String> {
+    let app_id = app.config()
+        .identifier
+        .clone();
+
+    ToastNotificationManager::History()
+        .and_then(|history| history.ClearWithId(&HSTRING::from(&app_id)))
+        .map_err(|e| format!("Failed to dismiss Windows notifications: {}", e))
+}
+
+/// Get the tags (notification ids) of our toasts still in Action Center
+#[cfg(target_os = "windows")]
+pub fn get_delivered_notifications(app: AppHandle) -> Result<Vec<String>, String> {
+    let app_id = app.config()
+        .identifier
+        .clone();
+
+    let delivered = || -> windows::core::Result<Vec<String>> {
+        let toasts = ToastNotificationManager::History()?.GetHistoryWithId(&HSTRING::from(&app_id))?;
+        (0..toasts.Size()?)
+            .map(|index| Ok(toasts.GetAt(index)?.Tag()?.to_string()))
+            .collect()
+    };
+
+    delivered().map_err(|e| format!("Failed to get Windows notifications: {}", e))
+}
+
+/// Check whether toasts are enabled for the app
+///
+/// Windows never prompts: toasts are allowed unless the user, an
+/// administrator or the app manifest has turned them off.
+#[cfg(target_os = "windows")]
+pub fn get_permission_status(app: AppHandle) -> Result<NotificationPermission, String> {
+    let app_id = app.config()
+        .identifier
+        .clone();
+
+    let setting = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(&app_id))
+        .and_then(|notifier| notifier.Setting())
+        .map_err(|e| format!("Failed to get Windows notification setting: {}", e))?;
+
+    let status = if setting == NotificationSetting::Enabled {
+        PermissionStatus::Granted
+    } else {
+        println!("🔔 Windows notifications are disabled: {:?}", setting);
+        PermissionStatus::Denied
+    };
+    Ok(NotificationPermission::new(status))
+}
+
+/// Work out which part of the toast was activated
+#[cfg(target_os = "windows")]
+fn activated_action(args: &Option<IInspectable>) -> ClickAction {
+    let Some(args) = args
+        .as_ref()
+        .and_then(|args| args.cast::<ToastActivatedEventArgs>().ok())
+    else {
+        return ClickAction::Body;
+    };
+
+    let arguments = args.Arguments().map(|a| a.to_string()).unwrap_or_default();
+
+    if arguments == REPLY_ACTION_ID {
+        let text = args
+            .UserInput()
+            .and_then(|input| input.Lookup(&HSTRING::from(REPLY_INPUT_ID)))
+            .and_then(|value| value.cast::<IPropertyValue>())
+            .and_then(|value| value.GetString())
+            .map(|text| text.to_string())
+            .unwrap_or_default();
+        return ClickAction::Reply(text);
+    }
+
+    if arguments.is_empty() {
+        ClickAction::Body
+    } else {
+        ClickAction::Button(arguments)
+    }
+}
+
+/// Get the reason a toast was dismissed
+#[cfg(target_os = "windows")]
+fn dismissal_reason(args: &Option<ToastDismissedEventArgs>) -> Option<ToastDismissalReason> {
+    args.as_ref().and_then(|args| args.Reason().ok())
+}
+
+/// Build the toast XML for a notification
+#[cfg(target_os = "windows")]
+fn toast_xml(request: &NotificationRequest) -> String {
+    let mut actions = String::new();
+
+    // Reply input box with its send button
+    if let Some(ref reply) = request.reply {
+        actions.push_str(&format!(
+            r#"<input id="{}" type="text" placeHolderContent="{}"/>"#,
+            REPLY_INPUT_ID,
+            escape_xml(reply.placeholder.as_deref().unwrap_or_default())
+        ));
+        actions.push_str(&format!(
+            r#"<action content="{}" arguments="{}" hint-inputId="{}"/>"#,
+            escape_xml(&reply.label),
+            REPLY_ACTION_ID,
+            REPLY_INPUT_ID
+        ));
+    }
+
+    // Button arguments come back through `Activated` as the action id
+    for action in &request.actions {
+        actions.push_str(&format!(
+            r#"<action content="{}" arguments="{}"/>"#,
+            escape_xml(&action.label),
+            escape_xml(&action.id)
+        ));
+    }
+
+    if !actions.is_empty() {
+        actions = format!("<actions>{}</actions>", actions);
+    }
+
+    // Avatars replace the app logo, attachments are shown inline
+    let image = match request.image {
+        Some(ref image) => {
+            let src = url::Url::from_file_path(&image.path)
+                .map(|url| url.to_string())
+                .unwrap_or_default();
+            match image.kind {
+                ImageKind::Avatar => format!(
+                    r#"<image placement="appLogoOverride" hint-crop="circle" src="{}"/>"#,
+                    escape_xml(&src)
+                ),
+                ImageKind::Attachment => format!(r#"<image src="{}"/>"#, escape_xml(&src)),
+            }
+        }
+        None => String::new(),
+    };
+
+    let audio = match request.sound {
+        // Named sounds are played by `play_bundled_sound` instead
+        Some(NotificationSound::None | NotificationSound::Named(_)) => {
+            r#"<audio silent="true"/>"#.to_string()
+        }
+        Some(NotificationSound::Default) | None => format!(r#"<audio src="{}"/>"#, DEFAULT_SOUND),
+    };
+
+    // Long toasts stay on screen for 25 seconds, urgent ones break through
+    // Focus Assist on Windows 11
+    let toast_attributes = match request.priority {
+        NotificationPriority::Low | NotificationPriority::Normal => r#"duration="short""#,
+        NotificationPriority::High => r#"duration="long""#,
+        NotificationPriority::Critical => r#"duration="long" scenario="urgent""#,
+    };
+
+    format!(
+        r#"<toast {}>
+            <visual>
+                <binding template="ToastGeneric">
+                    <text>{}</text>
+                    <text>{}</text>
+                    {}
+                </binding>
+            </visual>
+            {}
+            {}
+        </toast>"#,
+        toast_attributes,
+        escape_xml(&request.title),
+        escape_xml(&render_body(&request.body, BodyFormat::PlainText, MAX_BODY_LEN)),
+        image,
+        audio,
+        actions
+    )
+}
+
+/// Escape text for toast XML content and attributes
+#[cfg(target_os = "windows")]
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Notification backend using toast notifications
+#[cfg(target_os = "windows")]
+pub struct WindowsBackend {
+    pub app: AppHandle,
+}
+
+#[cfg(target_os = "windows")]
+impl NotificationBackend for WindowsBackend {
+    fn show(&self, request: &NotificationRequest, _capabilities: &NotificationCapabilities) -> Result<(), String> {
+        show_notification(self.app.clone(), request)
+    }
+
+    fn dismiss(&self, id: &str, thread_id: Option<&str>) -> Result<(), String> {
+        dismiss_notification(self.app.clone(), id, thread_id)
+    }
+
+    fn dismiss_all(&self) -> Result<(), String> {
+        dismiss_all_notifications(self.app.clone())
+    }
+
+    fn clear_thread(&self, thread_id: &str, _ids: &[String]) -> Result<(), String> {
+        clear_thread(self.app.clone(), thread_id)
+    }
+
+    fn delivered(&self) -> Result<Vec<String>, String> {
+        get_delivered_notifications(self.app.clone())
+    }
+
+    fn capabilities(&self) -> Result<NotificationCapabilities, String> {
+        Ok(NotificationCapabilities::native())
+    }
+
+    fn permission_status(&self) -> Result<NotificationPermission, String> {
+        get_permission_status(self.app.clone())
+    }
 }
 
 #[cfg(not(target_os = "windows"))]
 pub fn setup(_manager: Arc<NotificationManager>) {}
 
 #[cfg(not(target_os = "windows"))]
-pub fn show_notification(
-    _app: AppHandle,
-    _id: String,
-    _title: String,
-    _body: String,
-    _url: Option<String>,
-) -> Result<(), String> {
+pub fn show_notification(_app: AppHandle, _request: &NotificationRequest) -> Result<(), String> {
+    Err("Windows notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn clear_thread(_app: AppHandle, _thread_id: &str) -> Result<(), String> {
+    Err("Windows notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn dismiss_notification(_app: AppHandle, _id: &str, _thread_id: Option<&str>) -> Result<(), String> {
+    Err("Windows notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn dismiss_all_notifications(_app: AppHandle) -> Result<(), String> {
+    Err("Windows notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_permission_status(_app: AppHandle) -> Result<NotificationPermission, String> {
+    Err("Windows notifications not supported on this platform".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_delivered_notifications(_app: AppHandle) -> Result<Vec<String>, String> {
     Err("Windows notifications not supported on this platform".to_string())
 }
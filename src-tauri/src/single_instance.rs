@@ -0,0 +1,362 @@
+//! Single instance guard
+//!
+//! A second launch, usually from opening a `cushion://` link, hands its
+//! arguments to the running app and exits. The running app routes them
+//! through the deep link plugin like any other link and shows its window.
+//!
+//! Windows uses tauri-plugin-single-instance. On Linux the first instance
+//! locks a file and listens on a Unix socket, both in `$XDG_RUNTIME_DIR`; a
+//! later launch finds the lock taken, connects, writes its argv as a JSON
+//! array on one line and exits once the first instance answers `ok`. Only the
+//! lock holder ever replaces a socket left behind by a crash. Without a
+//! runtime dir there's nowhere private to put the socket, so the app starts
+//! without the guard. macOS needs neither: LaunchServices never starts a
+//! second instance and delivers links to the running one.
+
+#[cfg(target_os = "linux")]
+use std::fs::{File, OpenOptions, TryLockError};
+#[cfg(target_os = "linux")]
+use std::io::{self, BufRead, BufReader, Write};
+#[cfg(target_os = "linux")]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(target_os = "linux")]
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+
+/// How long either side waits on the other during the handoff
+#[cfg(target_os = "linux")]
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Reply confirming the arguments were received
+#[cfg(target_os = "linux")]
+const ACK: &str = "ok";
+
+/// How often, and how far apart, a later launch tries to reach a primary
+/// instance that holds the lock but may not be listening yet
+#[cfg(target_os = "linux")]
+const CONNECT_ATTEMPTS: u32 = 10;
+#[cfg(target_os = "linux")]
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Outcome of starting up
+#[cfg(target_os = "linux")]
+pub enum Launch {
+    /// No other instance is running; this one listens for later launches
+    Primary(PrimaryInstance),
+    /// The arguments were handed to the running instance
+    Forwarded,
+}
+
+/// The socket the first instance accepts later launches on
+#[cfg(target_os = "linux")]
+pub struct PrimaryInstance {
+    listener: UnixListener,
+    /// Held for as long as the app runs; the OS releases it if we crash
+    lock: File,
+}
+
+/// Become the primary instance, or hand `args` to the running one
+///
+/// The lock file next to `socket_path` decides which launch is primary, so
+/// the socket is only ever replaced by the instance about to listen on it.
+#[cfg(target_os = "linux")]
+pub fn acquire(socket_path: &Path, args: &[String]) -> io::Result<Launch> {
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(socket_path.with_extension("lock"))?;
+
+    match lock.try_lock() {
+        Ok(()) => {
+            // Whatever socket is there was left behind by a crash
+            match std::fs::remove_file(socket_path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            let listener = UnixListener::bind(socket_path)?;
+            Ok(Launch::Primary(PrimaryInstance { listener, lock }))
+        }
+        Err(TryLockError::WouldBlock) => {
+            // The primary may have just taken the lock and not be listening yet
+            let mut attempts = 0;
+            let stream = loop {
+                match UnixStream::connect(socket_path) {
+                    Ok(stream) => break stream,
+                    Err(e)
+                        if attempts + 1 < CONNECT_ATTEMPTS
+                            && matches!(
+                                e.kind(),
+                                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+                            ) =>
+                    {
+                        attempts += 1;
+                        std::thread::sleep(CONNECT_RETRY_DELAY);
+                    }
+                    Err(e) => return Err(e),
+                }
+            };
+            forward(stream, args)?;
+            Ok(Launch::Forwarded)
+        }
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Send our arguments to the primary instance and wait for its answer
+#[cfg(target_os = "linux")]
+fn forward(mut stream: UnixStream, args: &[String]) -> io::Result<()> {
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDOFF_TIMEOUT))?;
+
+    let mut message = serde_json::to_string(args)?;
+    message.push('\n');
+    stream.write_all(message.as_bytes())?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    if reply.trim_end() == ACK {
+        Ok(())
+    } else {
+        Err(io::Error::other(
+            "Primary instance did not acknowledge the launch",
+        ))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl PrimaryInstance {
+    /// Call `handler` with the arguments of each later launch, on a
+    /// background thread
+    pub fn serve<F>(self, handler: F) -> std::thread::JoinHandle<()>
+    where
+        F: Fn(Vec<String>) + Send + 'static,
+    {
+        let PrimaryInstance { listener, lock } = self;
+        std::thread::spawn(move || {
+            // Keep the lock for as long as we listen
+            let _lock = lock;
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| receive(stream, &handler));
+                if let Err(e) = result {
+                    eprintln!("❌ Failed to receive second launch: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Read one launch's arguments, acknowledge them and pass them on
+#[cfg(target_os = "linux")]
+fn receive<F: Fn(Vec<String>)>(stream: UnixStream, handler: &F) -> io::Result<()> {
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDOFF_TIMEOUT))?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let args: Vec<String> = serde_json::from_str(&line)?;
+
+    (&stream).write_all(format!("{}\n", ACK).as_bytes())?;
+    handler(args);
+    Ok(())
+}
+
+/// Path of the socket the primary instance listens on, if there's a
+/// runtime dir to put it in
+///
+/// The runtime dir is only accessible to its user, so nobody else can
+/// listen on the socket and receive our arguments. A shared directory such
+/// as `/tmp` would let another user claim the path first.
+#[cfg(target_os = "linux")]
+fn socket_path(identifier: &str) -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")?;
+    Some(PathBuf::from(dir).join(format!("{}.sock", identifier)))
+}
+
+/// Become the primary instance, or forward this launch's arguments to the
+/// running one and exit
+///
+/// If there's no runtime dir or the socket can't be used, the app starts
+/// anyway, just without the guard.
+#[cfg(target_os = "linux")]
+pub fn acquire_or_exit(identifier: &str) -> Option<PrimaryInstance> {
+    let Some(socket_path) = socket_path(identifier) else {
+        println!("⚠️  XDG_RUNTIME_DIR is not set, starting without the single instance check");
+        return None;
+    };
+
+    let args: Vec<String> = std::env::args().collect();
+    match acquire(&socket_path, &args) {
+        Ok(Launch::Primary(primary)) => Some(primary),
+        Ok(Launch::Forwarded) => {
+            println!("🪟 Cushion is already running, handed over the launch");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("❌ Single instance check failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Handle later launches: route their deep links and show the window
+#[cfg(target_os = "linux")]
+pub fn serve(app: &tauri::AppHandle, primary: PrimaryInstance) {
+    let app = app.clone();
+    primary.serve(move |args| on_second_launch(&app, args));
+}
+
+/// Called in the primary instance with a later launch's arguments
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+pub fn on_second_launch(app: &tauri::AppHandle, args: Vec<String>) {
+    println!("🪟 Second launch with arguments: {:?}", args);
+
+    // On Windows the plugin has already passed the arguments on
+    #[cfg(target_os = "linux")]
+    {
+        use tauri_plugin_deep_link::DeepLinkExt;
+        app.deep_link().handle_cli_arguments(args.iter());
+    }
+
+    if let Err(e) = crate::commands::window::show_main_window(app.clone()) {
+        eprintln!("❌ Failed to show window: {}", e);
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+    use std::sync::mpsc;
+
+    /// Set when the test binary is run as a second instance
+    const CHILD_SOCKET_ENV: &str = "CUSHION_TEST_SINGLE_INSTANCE_SOCKET";
+
+    fn temp_socket(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "cushion-single-instance-{}-{}.sock",
+            name,
+            std::process::id()
+        ));
+        cleanup(&path);
+        path
+    }
+
+    fn cleanup(path: &Path) {
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(path.with_extension("lock"));
+    }
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    fn primary(launch: Launch) -> PrimaryInstance {
+        match launch {
+            Launch::Primary(primary) => primary,
+            Launch::Forwarded => panic!("expected to become the primary instance"),
+        }
+    }
+
+    #[test]
+    fn test_second_launch_is_forwarded() {
+        let path = temp_socket("forward");
+        let first = primary(acquire(&path, &args(&["cushion"])).unwrap());
+
+        let (sender, received) = mpsc::channel();
+        first.serve(move |args| sender.send(args).unwrap());
+
+        let second = acquire(&path, &args(&["cushion", "cushion://post/1"])).unwrap();
+        assert!(matches!(second, Launch::Forwarded));
+        assert_eq!(
+            received.recv_timeout(HANDOFF_TIMEOUT).unwrap(),
+            args(&["cushion", "cushion://post/1"])
+        );
+
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_stale_socket_is_replaced() {
+        let path = temp_socket("stale");
+
+        // A crashed instance leaves its socket file behind
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        assert!(matches!(
+            acquire(&path, &args(&["cushion"])).unwrap(),
+            Launch::Primary(_)
+        ));
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_only_the_lock_holder_replaces_the_socket() {
+        let path = temp_socket("locked");
+
+        // Another launch holds the lock and hasn't started listening
+        let lock = File::create(path.with_extension("lock")).unwrap();
+        lock.lock().unwrap();
+        drop(UnixListener::bind(&path).unwrap());
+
+        assert!(acquire(&path, &args(&["cushion"])).is_err());
+        assert!(path.exists());
+
+        drop(lock);
+        cleanup(&path);
+    }
+
+    #[test]
+    fn test_garbage_is_not_acknowledged() {
+        let path = temp_socket("garbage");
+        let first = primary(acquire(&path, &args(&["cushion"])).unwrap());
+        first.serve(|_| panic!("garbage must not reach the handler"));
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream.write_all(b"not json\n").unwrap();
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "");
+
+        cleanup(&path);
+    }
+
+    /// Runs as the second instance in `test_second_process_hands_off`
+    #[test]
+    fn second_instance_child() {
+        let Some(path) = std::env::var_os(CHILD_SOCKET_ENV) else {
+            return;
+        };
+        let launch = acquire(Path::new(&path), &args(&["cushion", "cushion://thread/t1"])).unwrap();
+        assert!(matches!(launch, Launch::Forwarded));
+    }
+
+    #[test]
+    fn test_second_process_hands_off() {
+        let path = temp_socket("process");
+        let first = primary(acquire(&path, &args(&["cushion"])).unwrap());
+
+        let (sender, received) = mpsc::channel();
+        first.serve(move |args| sender.send(args).unwrap());
+
+        // Launch this test binary again, running only the child test
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "single_instance::tests::second_instance_child",
+                "--nocapture",
+            ])
+            .env(CHILD_SOCKET_ENV, &path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        assert_eq!(
+            received.recv_timeout(HANDOFF_TIMEOUT).unwrap(),
+            args(&["cushion", "cushion://thread/t1"])
+        );
+        cleanup(&path);
+    }
+}
@@ -0,0 +1,65 @@
+//! JSON persistence for app settings and state
+//!
+//! Small JSON files kept in the app config directory (settings) or the app
+//! data directory (state), written atomically so a crash mid-write never
+//! leaves a truncated file behind.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Path of a file in the app config directory
+pub fn config_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(file_name))
+        .map_err(|e| format!("Failed to resolve app config directory: {}", e))
+}
+
+/// Path of a file in the app data directory
+pub fn data_path(app: &AppHandle, file_name: &str) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(file_name))
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))
+}
+
+/// Load a JSON file from the app config directory
+///
+/// Returns `None` if the file doesn't exist or can't be parsed.
+pub fn load_json<T: DeserializeOwned>(app: &AppHandle, file_name: &str) -> Option<T> {
+    read_json(&config_path(app, file_name).ok()?)
+}
+
+/// Read a JSON file
+///
+/// Returns `None` if the file doesn't exist or can't be parsed.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+
+    match serde_json::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("⚠️ Ignoring invalid {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Write a value as JSON, creating the parent directory if needed
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let contents = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+
+    // Write to a temporary file first, then move it into place
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, contents)
+        .and_then(|()| fs::rename(&temp_path, path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,312 @@
+//! Update channels
+//!
+//! Each channel has its own manifest next to the one configured at build
+//! time (`latest.json`, `latest-beta.json`, `latest-dev.json`), so the
+//! channel can be switched at runtime without a different build. The choice
+//! is kept in `update-channel.json` in the app config directory; without it
+//! the build's own channel is used.
+//!
+//! Production and developer builds differ in identifier, product name, web
+//! app URL and link scheme (see `build-config.js`), so a build only switches
+//! between channels shipping its own flavour: Stable and Beta for production
+//! builds, Dev for developer builds.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use tauri::AppHandle;
+use tauri_plugin_updater::{Updater, UpdaterExt};
+use url::Url;
+
+/// File in the app config directory holding the chosen channel
+const CHANNEL_FILE: &str = "update-channel.json";
+
+/// Where updates come from, from most to least stable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Stable,
+    Beta,
+    Dev,
+}
+
+impl UpdateChannel {
+    pub const ALL: [UpdateChannel; 3] = [Self::Stable, Self::Beta, Self::Dev];
+
+    /// File name of the channel's update manifest
+    pub fn manifest_name(self) -> &'static str {
+        match self {
+            Self::Stable => "latest.json",
+            Self::Beta => "latest-beta.json",
+            Self::Dev => "latest-dev.json",
+        }
+    }
+
+    /// The channel whose manifest `endpoint` points at
+    fn from_endpoint(endpoint: &str) -> Option<Self> {
+        let name = endpoint.rsplit('/').next()?;
+        Self::ALL
+            .into_iter()
+            .find(|channel| channel.manifest_name() == name)
+    }
+
+    /// The kind of build the channel's releases are
+    pub fn flavour(self) -> BuildFlavour {
+        match self {
+            Self::Stable | Self::Beta => BuildFlavour::Production,
+            Self::Dev => BuildFlavour::Developer,
+        }
+    }
+
+    /// Name shown to the user
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Stable => "Stable",
+            Self::Beta => "Beta",
+            Self::Dev => "Dev",
+        }
+    }
+
+    /// Whether moving from `self` to `to` goes to a more stable channel,
+    /// which usually means installing an older version
+    pub fn is_downgrade_to(self, to: UpdateChannel) -> bool {
+        to < self
+    }
+}
+
+/// Which of the builds made by `build-config.js` a release is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildFlavour {
+    /// `Cushion.app`, identifier `com.cushion.desktop`
+    Production,
+    /// `Cushion Developer.app`, identifier `com.cushion.desktop.dev`
+    Developer,
+}
+
+/// The persisted channel choice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChannelSetting {
+    channel: UpdateChannel,
+    /// Set after the user confirmed switching to a more stable channel, so
+    /// its older release is offered as an update; cleared once it's installed
+    #[serde(default)]
+    allow_downgrade: bool,
+}
+
+/// The update endpoint configured at build time in `tauri.conf.json`
+fn configured_endpoint(app: &AppHandle) -> Option<String> {
+    app.config()
+        .plugins
+        .0
+        .get("updater")?
+        .get("endpoints")?
+        .get(0)?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// The channel this build was configured for
+fn build_channel(app: &AppHandle) -> UpdateChannel {
+    configured_endpoint(app)
+        .and_then(|endpoint| UpdateChannel::from_endpoint(&endpoint))
+        .unwrap_or(UpdateChannel::Stable)
+}
+
+fn load_setting(app: &AppHandle) -> ChannelSetting {
+    let build = build_channel(app);
+    crate::storage::load_json(app, CHANNEL_FILE)
+        .filter(|setting: &ChannelSetting| setting.channel.flavour() == build.flavour())
+        .unwrap_or(ChannelSetting {
+            channel: build,
+            allow_downgrade: false,
+        })
+}
+
+fn save_setting(app: &AppHandle, setting: &ChannelSetting) -> Result<(), String> {
+    crate::storage::write_json(&crate::storage::config_path(app, CHANNEL_FILE)?, setting)
+}
+
+/// The channel updates are checked on
+pub fn current_channel(app: &AppHandle) -> UpdateChannel {
+    load_setting(app).channel
+}
+
+/// The channels shipping the same flavour as `build`
+fn channels_like(build: UpdateChannel) -> Vec<UpdateChannel> {
+    UpdateChannel::ALL
+        .into_iter()
+        .filter(|channel| channel.flavour() == build.flavour())
+        .collect()
+}
+
+/// The channels this build can switch to
+pub fn available_channels(app: &AppHandle) -> Vec<UpdateChannel> {
+    channels_like(build_channel(app))
+}
+
+/// Stop offering older releases once the downgrade they were allowed for
+/// has been installed, or the current channel's release is already running
+pub fn finish_downgrade(app: &AppHandle) {
+    let setting = load_setting(app);
+    if !setting.allow_downgrade {
+        return;
+    }
+
+    let setting = ChannelSetting {
+        allow_downgrade: false,
+        ..setting
+    };
+    if let Err(e) = save_setting(app, &setting) {
+        eprintln!("❌ Failed to save update channel: {}", e);
+    }
+}
+
+/// The setting after switching from `current` to `channel`
+fn switched(current: ChannelSetting, channel: UpdateChannel) -> ChannelSetting {
+    let allow_downgrade = match channel.cmp(&current.channel) {
+        Ordering::Less => true,
+        Ordering::Greater => false,
+        Ordering::Equal => current.allow_downgrade,
+    };
+    ChannelSetting {
+        channel,
+        allow_downgrade,
+    }
+}
+
+/// Switch to `channel`, asking the user to confirm first if it's a
+/// downgrade
+///
+/// Returns whether the channel was switched.
+pub async fn switch_channel(app: &AppHandle, channel: UpdateChannel) -> Result<bool, String> {
+    if !available_channels(app).contains(&channel) {
+        return Err(format!(
+            "The {} channel ships a different build; install it separately",
+            channel.label()
+        ));
+    }
+
+    let current = load_setting(app);
+    if current.channel.is_downgrade_to(channel)
+        && !super::dialog::confirm_channel_downgrade(app, current.channel, channel).await
+    {
+        println!("ℹ️  User kept the {} channel", current.channel.label());
+        return Ok(false);
+    }
+
+    let setting = switched(current, channel);
+    save_setting(app, &setting)?;
+    println!("🔀 Update channel set to {}", channel.label());
+    Ok(true)
+}
+
+/// The manifest URL for `channel`, next to the configured `endpoint`
+fn channel_endpoint(endpoint: &str, channel: UpdateChannel) -> Result<Url, String> {
+    Url::parse(endpoint)
+        .and_then(|url| url.join(channel.manifest_name()))
+        .map_err(|e| format!("Invalid update endpoint {}: {}", endpoint, e))
+}
+
+/// Build an updater checking the current channel
+pub fn build_updater(app: &AppHandle) -> Result<Updater, String> {
+    let setting = load_setting(app);
+    let mut builder = app.updater_builder();
+
+    if let Some(endpoint) = configured_endpoint(app) {
+        let url = channel_endpoint(&endpoint, setting.channel)?;
+        builder = builder.endpoints(vec![url]).map_err(|e| e.to_string())?;
+    }
+
+    if setting.allow_downgrade {
+        // Any release other than the running one, so a more stable
+        // channel's older release replaces a newer prerelease
+        builder = builder.version_comparator(|current, release| release.version != current);
+    }
+
+    builder.build().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINT: &str =
+        "https://github.com/cushioncomputing/cushion-desktop/releases/latest/download/latest-dev.json";
+
+    #[test]
+    fn test_channel_endpoints_share_the_configured_location() {
+        assert_eq!(
+            channel_endpoint(ENDPOINT, UpdateChannel::Stable).unwrap().as_str(),
+            "https://github.com/cushioncomputing/cushion-desktop/releases/latest/download/latest.json"
+        );
+        assert_eq!(
+            channel_endpoint(ENDPOINT, UpdateChannel::Beta).unwrap().as_str(),
+            "https://github.com/cushioncomputing/cushion-desktop/releases/latest/download/latest-beta.json"
+        );
+        assert!(channel_endpoint("not a url", UpdateChannel::Dev).is_err());
+    }
+
+    #[test]
+    fn test_channel_of_endpoint() {
+        for channel in UpdateChannel::ALL {
+            let endpoint = channel_endpoint(ENDPOINT, channel).unwrap();
+            assert_eq!(UpdateChannel::from_endpoint(endpoint.as_str()), Some(channel));
+        }
+        assert_eq!(UpdateChannel::from_endpoint("https://example.com/update.json"), None);
+    }
+
+    #[test]
+    fn test_channels_keep_the_build_flavour() {
+        use UpdateChannel::*;
+
+        assert_eq!(channels_like(Stable), vec![Stable, Beta]);
+        assert_eq!(channels_like(Beta), vec![Stable, Beta]);
+        assert_eq!(channels_like(Dev), vec![Dev]);
+    }
+
+    #[test]
+    fn test_downgrades() {
+        use UpdateChannel::*;
+
+        assert!(Dev.is_downgrade_to(Beta));
+        assert!(Dev.is_downgrade_to(Stable));
+        assert!(Beta.is_downgrade_to(Stable));
+        assert!(!Stable.is_downgrade_to(Beta));
+        assert!(!Beta.is_downgrade_to(Dev));
+        assert!(!Beta.is_downgrade_to(Beta));
+    }
+
+    #[test]
+    fn test_switching_down_allows_an_older_release() {
+        let setting = |channel, allow_downgrade| ChannelSetting {
+            channel,
+            allow_downgrade,
+        };
+        use UpdateChannel::*;
+
+        assert_eq!(switched(setting(Dev, false), Stable), setting(Stable, true));
+        assert_eq!(switched(setting(Stable, true), Beta), setting(Beta, false));
+        assert_eq!(switched(setting(Beta, false), Dev), setting(Dev, false));
+        assert_eq!(switched(setting(Stable, true), Stable), setting(Stable, true));
+    }
+
+    #[test]
+    fn test_setting_format() {
+        let setting: ChannelSetting = serde_json::from_str(r#"{ "channel": "beta" }"#).unwrap();
+        assert_eq!(
+            setting,
+            ChannelSetting {
+                channel: UpdateChannel::Beta,
+                allow_downgrade: false,
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(ChannelSetting {
+                channel: UpdateChannel::Stable,
+                allow_downgrade: true,
+            })
+            .unwrap(),
+            serde_json::json!({ "channel": "stable", "allowDowngrade": true })
+        );
+    }
+}
@@ -2,8 +2,13 @@
 //!
 //! Provides UI for update prompts and installation.
 
+use tauri::Manager;
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons};
-use tauri_plugin_updater::UpdaterExt;
+
+use super::{
+    build_updater, emit_state, restart_to_update, stage_update, PendingUpdate, UpdateChannel,
+    UpdateState,
+};
 
 /// Show update dialog and handle installation
 ///
@@ -14,11 +19,11 @@ pub fn show_update_dialog(handle: &tauri::AppHandle, show_up_to_date: bool) {
     let app = handle.clone();
 
     tauri::async_runtime::spawn(async move {
-        match app.updater_builder().build() {
+        match build_updater(&app) {
             Ok(updater) => {
                 match updater.check().await {
                     Ok(Some(update)) => {
-                        prompt_and_install_update(&app, &update).await;
+                        prompt_and_install_update(&app, update).await;
                     }
                     Ok(None) => {
                         println!("✅ App is up to date");
@@ -44,10 +49,22 @@ pub fn show_update_dialog(handle: &tauri::AppHandle, show_up_to_date: bool) {
     });
 }
 
-/// Prompt user to install update and handle the installation
-async fn prompt_and_install_update(app: &tauri::AppHandle, update: &tauri_plugin_updater::Update) {
+/// Prompt user to install update, downloading it in the background
+async fn prompt_and_install_update(app: &tauri::AppHandle, update: tauri_plugin_updater::Update) {
     println!("✅ Update available: {}", update.version);
 
+    let pending = app.state::<PendingUpdate>();
+    pending.set_available(&update.version);
+    emit_state(app);
+
+    // Already downloaded in the background, so only the restart is left
+    if let Some(UpdateState::Ready { version }) = pending.state() {
+        if version == update.version {
+            prompt_restart(app, &version);
+            return;
+        }
+    }
+
     let app_for_dialog = app.clone();
     let confirmed = tauri::async_runtime::spawn_blocking(move || {
         app_for_dialog.dialog()
@@ -59,23 +76,12 @@ async fn prompt_and_install_update(app: &tauri::AppHandle, update: &tauri_plugin
 
     if confirmed {
         println!("✅ User confirmed update installation");
-        println!("⬇️  Installing update: {}", update.version);
+        let version = update.version.clone();
+        stage_update(app, update).await;
 
-        match update.download_and_install(|chunk_length, content_length| {
-            if let Some(total) = content_length {
-                let percentage = (chunk_length as f64 / total as f64) * 100.0;
-                println!("📊 Download progress: {:.1}%", percentage);
-            }
-        }, || {
-            println!("✅ Update downloaded, installing...");
-        }).await {
-            Ok(_) => {
-                println!("🎉 Update installed! Restarting...");
-                app.restart();
-            }
-            Err(e) => {
-                println!("❌ Failed to install update: {}", e);
-                show_error_dialog(app, "Failed to install update. Please try again later.").await;
+        if let Some(UpdateState::Failed { version: failed, .. }) = pending.state() {
+            if failed == version {
+                show_error_dialog(app, "Failed to download update. Please try again later.").await;
             }
         }
     } else {
@@ -83,6 +89,52 @@ async fn prompt_and_install_update(app: &tauri::AppHandle, update: &tauri_plugin
     }
 }
 
+/// Offer to restart into a downloaded update without blocking
+///
+/// If the user picks "Later" the update is installed when the app quits.
+pub fn prompt_restart(app: &tauri::AppHandle, version: &str) {
+    let handle = app.clone();
+    app.dialog()
+        .message(format!("Cushion {} is ready to install. Restart now to update, or it will be installed when you quit.", version))
+        .title("Software Update")
+        .buttons(MessageDialogButtons::OkCancelCustom("Restart to Update".into(), "Later".into()))
+        .show(move |restart| {
+            if !restart {
+                println!("ℹ️  Update will be installed on quit");
+                return;
+            }
+            if let Err(e) = restart_to_update(&handle) {
+                println!("❌ {}", e);
+                tauri::async_runtime::spawn(async move {
+                    show_error_dialog(&handle, "Failed to install update. Please try again later.").await;
+                });
+            }
+        });
+}
+
+/// Ask before switching to a more stable channel, which installs an older
+/// version
+pub(super) async fn confirm_channel_downgrade(
+    app: &tauri::AppHandle,
+    from: UpdateChannel,
+    to: UpdateChannel,
+) -> bool {
+    let app_clone = app.clone();
+    let message = format!(
+        "Switching from the {} channel to the {} channel will install the latest {} release, which may be older than the version you're using now.",
+        from.label(),
+        to.label(),
+        to.label()
+    );
+    tauri::async_runtime::spawn_blocking(move || {
+        app_clone.dialog()
+            .message(message)
+            .title("Switch Update Channel")
+            .buttons(MessageDialogButtons::OkCancelCustom(format!("Switch to {}", to.label()), "Cancel".into()))
+            .blocking_show()
+    }).await.unwrap_or(false)
+}
+
 /// Show "up to date" dialog
 async fn show_up_to_date_dialog(app: &tauri::AppHandle) {
     let app_clone = app.clone();
@@ -0,0 +1,201 @@
+//! Background download and deferred install
+//!
+//! Downloading is separate from installing: an update is downloaded and
+//! verified in the background, then written to the cache directory until the
+//! user restarts to update or quits the app. A download can be cancelled,
+//! leaving the update available to download again.
+
+use std::path::PathBuf;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_updater::Update;
+
+use super::progress::ProgressTracker;
+use super::{emit_state, staging, PendingUpdate, UpdateState};
+
+/// Where downloaded updates are kept until they're installed
+fn staging_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(staging::STAGING_DIR))
+        .map_err(|e| format!("Failed to resolve app cache directory: {}", e))
+}
+
+/// The key update signatures are checked against, from `tauri.conf.json`
+fn updater_pubkey(app: &AppHandle) -> Result<String, String> {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| "No updater public key is configured".to_string())
+}
+
+/// Download `update` and stage it, emitting `update-progress` events
+///
+/// Succeeds straight away if it's already downloaded, including by an earlier
+/// run of the app, and waits for it if it's already downloading, e.g. in the
+/// background. The download stops early if `cancel_update_download` is
+/// called.
+pub async fn download_update(app: &AppHandle, update: Update) -> Result<(), String> {
+    let pending = app.state::<PendingUpdate>();
+    let version = update.version.clone();
+
+    let Some(token) = pending.begin_download(&version) else {
+        println!("ℹ️  Waiting for update {} to download", version);
+        pending.wait_for_download().await;
+        return match pending.state() {
+            Some(UpdateState::Ready { .. }) => Ok(()),
+            Some(UpdateState::Failed { error, .. }) => {
+                Err(format!("Failed to download update: {}", error))
+            }
+            _ => Err("Update download cancelled".to_string()),
+        };
+    };
+
+    let dir = match staging_dir(app) {
+        Ok(dir) => dir,
+        Err(e) => {
+            pending.fail(&version, e.clone());
+            emit_state(app);
+            return Err(e);
+        }
+    };
+    let reusable = updater_pubkey(app)
+        .ok()
+        .and_then(|pubkey| staging::find(&dir, &version, &update.signature, &pubkey));
+    if let Some(path) = reusable {
+        println!("📦 Update {} was already downloaded", version);
+        pending.stage(update, path);
+        emit_state(app);
+        return Ok(());
+    }
+    emit_state(app);
+
+    println!("⬇️  Downloading update: {}", version);
+    let mut tracker = ProgressTracker::new(&version, Instant::now());
+    let download = update.download(
+        |chunk_length, content_length| {
+            let progress = tracker.record(chunk_length, content_length, Instant::now());
+            pending.set_progress(tracker.downloaded(), tracker.total());
+            if let Some(progress) = progress {
+                emit_state(app);
+                let _ = app.emit("update-progress", progress);
+            }
+        },
+        || println!("✅ Update {} downloaded", version),
+    );
+
+    let result = tokio::select! {
+        result = download => result,
+        () = token.cancelled() => {
+            println!("🛑 Update download cancelled");
+            pending.cancelled(&version);
+            emit_state(app);
+            return Err("Update download cancelled".to_string());
+        }
+    };
+
+    let staged = match result {
+        Ok(bytes) => {
+            let signature = update.signature.clone();
+            let write_version = version.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                staging::write(&dir, &write_version, &signature, &bytes)
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|written| written)
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    match staged {
+        Ok(path) => {
+            pending.stage(update, path);
+            emit_state(app);
+            Ok(())
+        }
+        Err(e) => {
+            println!("❌ Failed to download update: {}", e);
+            pending.fail(&version, e.clone());
+            emit_state(app);
+            Err(format!("Failed to download update: {}", e))
+        }
+    }
+}
+
+/// Download `update` in the background and stage it, then offer to restart
+///
+/// Does nothing if the same version is already downloading; if it's already
+/// downloaded the restart is offered again.
+pub async fn stage_update(app: &AppHandle, update: Update) {
+    if download_update(app, update).await.is_ok() {
+        offer_restart(app);
+    }
+}
+
+/// Offer to restart now if the window has focus, otherwise when it next
+/// gains it
+fn offer_restart(app: &AppHandle) {
+    let pending = app.state::<PendingUpdate>();
+    let focused = app
+        .get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+
+    if focused {
+        if let Some(UpdateState::Ready { version }) = pending.state() {
+            super::prompt_restart(app, &version);
+        }
+    } else {
+        pending.defer_prompt();
+    }
+}
+
+/// Install the staged update, if there is one
+///
+/// Returns whether an update was installed.
+pub fn install_staged_update(app: &AppHandle) -> Result<bool, String> {
+    let pending = app.state::<PendingUpdate>();
+    let Some(staged) = pending.take_staged() else {
+        return Ok(false);
+    };
+
+    let version = staged.update.version.clone();
+    println!("📦 Installing update: {}", version);
+    // Checked again, since the file may have changed since it was staged
+    let installed = updater_pubkey(app)
+        .and_then(|pubkey| staging::read(&staged.path, &staged.update.signature, &pubkey))
+        .and_then(|bytes| staged.update.install(bytes).map_err(|e| e.to_string()));
+
+    // Installed, or unusable and downloaded again next time
+    if let Some(dir) = staged.path.parent() {
+        staging::clear(dir);
+    }
+
+    match installed {
+        Ok(()) => {
+            println!("🎉 Update {} installed", version);
+            super::finish_downgrade(app);
+            Ok(true)
+        }
+        Err(e) => {
+            println!("❌ Failed to install update: {}", e);
+            pending.fail(&version, e.to_string());
+            emit_state(app);
+            Err(format!("Failed to install update: {}", e))
+        }
+    }
+}
+
+/// Install the staged update and restart into it
+pub fn restart_to_update(app: &AppHandle) -> Result<(), String> {
+    if install_staged_update(app)? {
+        println!("🔄 Restarting to finish the update...");
+        app.restart();
+    }
+    Err("No update is ready to install".to_string())
+}
@@ -1,32 +1,228 @@
 //! Update management module
 //!
 //! Handles update checking, dialogs, and scheduling for the Cushion desktop app.
+//!
+//! Updates are downloaded in the background and staged in the app cache
+//! directory; the user can restart to install one straight away, and
+//! otherwise it's installed when the app quits.
 
+mod channel;
 mod dialog;
+mod download;
+mod progress;
 mod scheduler;
+mod staging;
 
+use serde::Serialize;
+use std::path::PathBuf;
 use std::sync::Mutex;
-use tauri_plugin_updater::UpdaterExt;
+use tauri::{Emitter, Manager};
+use tauri_plugin_updater::Update;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
 
-pub use dialog::show_update_dialog;
+pub use channel::{
+    available_channels, build_updater, current_channel, finish_downgrade, switch_channel, UpdateChannel,
+};
+pub use dialog::{prompt_restart, show_update_dialog};
+pub use download::{download_update, install_staged_update, restart_to_update, stage_update};
 pub use scheduler::setup_auto_update_check;
 
-/// State to track pending updates that should be shown when window gains focus
-pub struct PendingUpdate(pub Mutex<Option<String>>);
+/// Progress of the latest update, from found to ready to install
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum UpdateState {
+    Available {
+        version: String,
+    },
+    Downloading {
+        version: String,
+        /// Bytes downloaded so far
+        downloaded: u64,
+        /// Size of the update, if the server sent it
+        total: Option<u64>,
+    },
+    /// Downloaded and verified; installed on restart or quit
+    Ready {
+        version: String,
+    },
+    Failed {
+        version: String,
+        error: String,
+    },
+}
+
+/// A downloaded update waiting to be installed
+pub struct StagedUpdate {
+    pub update: Update,
+    /// The verified download, in the app cache directory
+    pub path: PathBuf,
+}
+
+#[derive(Default)]
+struct Pending {
+    state: Option<UpdateState>,
+    /// Offer to restart next time the window gains focus
+    prompt_on_focus: bool,
+    /// Stops the download in progress
+    cancel: Option<CancellationToken>,
+}
+
+/// State of the update being downloaded in the background
+pub struct PendingUpdate {
+    pending: Mutex<Pending>,
+    staged: Mutex<Option<StagedUpdate>>,
+    /// Bumped whenever a download finishes, fails or is cancelled
+    settled: watch::Sender<u64>,
+}
 
 impl PendingUpdate {
     pub fn new() -> Self {
-        Self(Mutex::new(None))
+        Self {
+            pending: Mutex::new(Pending::default()),
+            staged: Mutex::new(None),
+            settled: watch::Sender::new(0),
+        }
+    }
+
+    pub fn state(&self) -> Option<UpdateState> {
+        self.pending.lock().unwrap().state.clone()
+    }
+
+    /// Record that `version` is available, unless it's already downloading
+    /// or downloaded
+    pub fn set_available(&self, version: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        if !is_in_progress(&pending.state, version) {
+            pending.state = Some(UpdateState::Available {
+                version: version.to_string(),
+            });
+        }
+    }
+
+    /// Start downloading `version`, returning the token that cancels it
+    ///
+    /// Returns `None` if it's already downloading or downloaded, so one
+    /// download runs at a time.
+    pub fn begin_download(&self, version: &str) -> Option<CancellationToken> {
+        let mut pending = self.pending.lock().unwrap();
+        if is_in_progress(&pending.state, version) {
+            return None;
+        }
+        pending.state = Some(UpdateState::Downloading {
+            version: version.to_string(),
+            downloaded: 0,
+            total: None,
+        });
+        let token = CancellationToken::new();
+        pending.cancel = Some(token.clone());
+        Some(token)
+    }
+
+    /// Cancel the download in progress
+    ///
+    /// Returns whether there was one to cancel.
+    pub fn cancel_download(&self) -> bool {
+        match self.pending.lock().unwrap().cancel.take() {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record that the download of `version` was cancelled; the update is
+    /// still available
+    pub fn cancelled(&self, version: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.cancel = None;
+        pending.state = Some(UpdateState::Available {
+            version: version.to_string(),
+        });
+        drop(pending);
+        self.settle();
+    }
+
+    /// Record how much of the download has arrived, as counted by its
+    /// `ProgressTracker`
+    pub fn set_progress(&self, bytes: u64, content_length: Option<u64>) {
+        let mut pending = self.pending.lock().unwrap();
+        if let Some(UpdateState::Downloading {
+            downloaded, total, ..
+        }) = pending.state.as_mut()
+        {
+            *downloaded = bytes;
+            *total = content_length;
+        }
+    }
+
+    /// Keep a downloaded update to install on restart or quit
+    pub fn stage(&self, update: Update, path: PathBuf) {
+        let version = update.version.clone();
+        *self.staged.lock().unwrap() = Some(StagedUpdate { update, path });
+        self.mark_ready(&version);
+    }
+
+    fn mark_ready(&self, version: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.cancel = None;
+        pending.state = Some(UpdateState::Ready {
+            version: version.to_string(),
+        });
+        drop(pending);
+        self.settle();
+    }
+
+    pub fn fail(&self, version: &str, error: String) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.cancel = None;
+        pending.state = Some(UpdateState::Failed {
+            version: version.to_string(),
+            error,
+        });
+        drop(pending);
+        self.settle();
+    }
+
+    /// Wake everyone waiting for the download in progress
+    fn settle(&self) {
+        self.settled.send_modify(|count| *count += 1);
+    }
+
+    /// Wait until no download is in progress
+    pub async fn wait_for_download(&self) {
+        // Subscribe before looking at the state, so a download finishing
+        // in between still wakes us
+        let mut settled = self.settled.subscribe();
+        while matches!(self.state(), Some(UpdateState::Downloading { .. })) {
+            if settled.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Take the downloaded update to install it
+    pub fn take_staged(&self) -> Option<StagedUpdate> {
+        self.staged.lock().unwrap().take()
     }
 
-    /// Set a pending update version
-    pub fn set(&self, version: String) {
-        *self.0.lock().unwrap() = Some(version);
+    /// Offer to restart when the window next gains focus
+    pub fn defer_prompt(&self) {
+        self.pending.lock().unwrap().prompt_on_focus = true;
     }
 
-    /// Take the pending update (clears it)
-    pub fn take(&self) -> Option<String> {
-        self.0.lock().unwrap().take()
+    /// The version to offer a restart for now the window has focus, if a
+    /// prompt was deferred (clears it)
+    pub fn take_prompt(&self) -> Option<String> {
+        let mut pending = self.pending.lock().unwrap();
+        if !std::mem::take(&mut pending.prompt_on_focus) {
+            return None;
+        }
+        match &pending.state {
+            Some(UpdateState::Ready { version }) => Some(version.clone()),
+            _ => None,
+        }
     }
 }
 
@@ -36,20 +232,35 @@ impl Default for PendingUpdate {
     }
 }
 
+fn is_in_progress(state: &Option<UpdateState>, version: &str) -> bool {
+    matches!(
+        state,
+        Some(UpdateState::Downloading { version: v, .. } | UpdateState::Ready { version: v }) if v == version
+    )
+}
+
+/// Tell the webview how the update is progressing
+pub fn emit_state(app: &tauri::AppHandle) {
+    if let Some(state) = app.state::<PendingUpdate>().state() {
+        let _ = app.emit("update-state", state);
+    }
+}
+
 /// Check for updates silently without showing any UI
-/// Returns the version string if an update is available
-pub async fn check_for_update_silent(app: &tauri::AppHandle) -> Option<String> {
+/// Returns the update if one is available
+pub async fn check_for_update_silent(app: &tauri::AppHandle) -> Option<Update> {
     println!("🔄 Checking for updates...");
 
-    match app.updater_builder().build() {
+    match build_updater(app) {
         Ok(updater) => {
             match updater.check().await {
                 Ok(Some(update)) => {
                     println!("✅ Update available: {}", update.version);
-                    Some(update.version.clone())
+                    Some(update)
                 }
                 Ok(None) => {
                     println!("✅ App is up to date");
+                    finish_downgrade(app);
                     None
                 }
                 Err(e) => {
@@ -64,3 +275,145 @@ pub async fn check_for_update_silent(app: &tauri::AppHandle) -> Option<String> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_progress_is_recorded() {
+        let pending = PendingUpdate::new();
+        pending.set_available("1.2.0");
+        assert_eq!(
+            pending.state(),
+            Some(UpdateState::Available {
+                version: "1.2.0".to_string()
+            })
+        );
+
+        assert!(pending.begin_download("1.2.0").is_some());
+        pending.set_progress(400, Some(1000));
+        pending.set_progress(750, Some(1000));
+        assert_eq!(
+            pending.state(),
+            Some(UpdateState::Downloading {
+                version: "1.2.0".to_string(),
+                downloaded: 750,
+                total: Some(1000),
+            })
+        );
+
+        pending.mark_ready("1.2.0");
+        assert_eq!(
+            pending.state(),
+            Some(UpdateState::Ready {
+                version: "1.2.0".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_one_download_per_version() {
+        let pending = PendingUpdate::new();
+        assert!(pending.begin_download("1.2.0").is_some());
+        assert!(pending.begin_download("1.2.0").is_none());
+
+        // A later check finding the same version leaves the download alone
+        pending.set_available("1.2.0");
+        assert!(matches!(
+            pending.state(),
+            Some(UpdateState::Downloading { .. })
+        ));
+
+        pending.mark_ready("1.2.0");
+        assert!(pending.begin_download("1.2.0").is_none());
+
+        // A newer release replaces it, and a failed download is retried
+        assert!(pending.begin_download("1.3.0").is_some());
+        pending.fail("1.3.0", "connection reset".to_string());
+        assert!(pending.begin_download("1.3.0").is_some());
+    }
+
+    #[test]
+    fn test_cancelling_a_download() {
+        let pending = PendingUpdate::new();
+        assert!(!pending.cancel_download());
+
+        let token = pending.begin_download("1.2.0").unwrap();
+        assert!(pending.cancel_download());
+        assert!(token.is_cancelled());
+        assert!(!pending.cancel_download());
+
+        // The update can be downloaded again
+        pending.cancelled("1.2.0");
+        assert_eq!(
+            pending.state(),
+            Some(UpdateState::Available {
+                version: "1.2.0".to_string()
+            })
+        );
+        let token = pending.begin_download("1.2.0").unwrap();
+
+        // Nothing is left to cancel once the download finishes
+        pending.mark_ready("1.2.0");
+        assert!(!pending.cancel_download());
+        assert!(!token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_waiting_for_a_download() {
+        let pending = std::sync::Arc::new(PendingUpdate::new());
+
+        // Nothing to wait for
+        pending.wait_for_download().await;
+
+        pending.begin_download("1.2.0").unwrap();
+        let waiter = {
+            let pending = pending.clone();
+            tokio::spawn(async move {
+                pending.wait_for_download().await;
+                pending.state()
+            })
+        };
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        pending.fail("1.2.0", "connection reset".to_string());
+        assert!(matches!(
+            waiter.await.unwrap(),
+            Some(UpdateState::Failed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_deferred_prompt_needs_a_ready_update() {
+        let pending = PendingUpdate::new();
+        pending.defer_prompt();
+        assert_eq!(pending.take_prompt(), None);
+
+        pending.mark_ready("1.2.0");
+        assert_eq!(pending.take_prompt(), None);
+
+        pending.defer_prompt();
+        assert_eq!(pending.take_prompt(), Some("1.2.0".to_string()));
+        assert_eq!(pending.take_prompt(), None);
+    }
+
+    #[test]
+    fn test_state_payload_shape() {
+        let state = UpdateState::Downloading {
+            version: "1.2.0".to_string(),
+            downloaded: 10,
+            total: None,
+        };
+        assert_eq!(
+            serde_json::to_value(state).unwrap(),
+            serde_json::json!({
+                "status": "downloading",
+                "version": "1.2.0",
+                "downloaded": 10,
+                "total": null
+            })
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Download progress reporting
+//!
+//! The updater reports each chunk as it arrives. Chunks are added up here,
+//! the one count behind both the `update-state` and `update-progress`
+//! events, and turned into `update-progress` events with the download rate and time
+//! left, sent a few times a second so the webview can show a progress bar.
+
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Least time between two progress events
+const EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Payload of the `update-progress` event emitted to the webview
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProgress {
+    pub version: String,
+    /// Bytes downloaded so far
+    pub downloaded: u64,
+    /// Size of the update, if the server sent it
+    pub total: Option<u64>,
+    /// Average download rate so far
+    pub bytes_per_second: u64,
+    /// Estimated time left, once the size and rate are known
+    pub eta_seconds: Option<u64>,
+}
+
+/// Adds up downloaded chunks and decides when to report progress
+pub struct ProgressTracker {
+    version: String,
+    started: Instant,
+    downloaded: u64,
+    total: Option<u64>,
+    last_emit: Option<Instant>,
+}
+
+impl ProgressTracker {
+    pub fn new(version: &str, started: Instant) -> Self {
+        Self {
+            version: version.to_string(),
+            started,
+            downloaded: 0,
+            total: None,
+            last_emit: None,
+        }
+    }
+
+    /// Bytes downloaded so far
+    pub fn downloaded(&self) -> u64 {
+        self.downloaded
+    }
+
+    /// Size of the update, if the server sent it
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    /// Count a chunk received at `now`, returning progress to report if
+    /// enough time has passed since the last report or the download is done
+    pub fn record(
+        &mut self,
+        chunk_length: usize,
+        content_length: Option<u64>,
+        now: Instant,
+    ) -> Option<UpdateProgress> {
+        self.downloaded += chunk_length as u64;
+        self.total = content_length.or(self.total);
+
+        let finished = self.total.is_some_and(|total| self.downloaded >= total);
+        let due = self
+            .last_emit
+            .is_none_or(|last| now.duration_since(last) >= EMIT_INTERVAL);
+        if !finished && !due {
+            return None;
+        }
+
+        self.last_emit = Some(now);
+        Some(self.progress(now))
+    }
+
+    fn progress(&self, now: Instant) -> UpdateProgress {
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        let bytes_per_second = if elapsed > 0.0 {
+            (self.downloaded as f64 / elapsed) as u64
+        } else {
+            0
+        };
+        let eta_seconds = match self.total {
+            Some(total) if bytes_per_second > 0 => {
+                Some(total.saturating_sub(self.downloaded).div_ceil(bytes_per_second))
+            }
+            _ => None,
+        };
+
+        UpdateProgress {
+            version: self.version.clone(),
+            downloaded: self.downloaded,
+            total: self.total,
+            bytes_per_second,
+            eta_seconds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_is_cumulative() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new("1.2.0", start);
+
+        let first = tracker.record(1_000, Some(4_000), start).unwrap();
+        assert_eq!(first.downloaded, 1_000);
+
+        let later = start + Duration::from_secs(1);
+        let progress = tracker.record(1_000, Some(4_000), later).unwrap();
+        assert_eq!(
+            progress,
+            UpdateProgress {
+                version: "1.2.0".to_string(),
+                downloaded: 2_000,
+                total: Some(4_000),
+                bytes_per_second: 2_000,
+                eta_seconds: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn test_reports_are_throttled_but_completion_is_not() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new("1.2.0", start);
+
+        assert!(tracker.record(100, Some(300), start).is_some());
+        let soon = start + Duration::from_millis(10);
+        assert!(tracker.record(100, Some(300), soon).is_none());
+
+        let done = tracker.record(100, Some(300), soon).unwrap();
+        assert_eq!(done.downloaded, 300);
+        assert_eq!(done.eta_seconds, Some(0));
+    }
+
+    #[test]
+    fn test_unknown_size_has_no_eta() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new("1.2.0", start);
+
+        let progress = tracker
+            .record(500, None, start + Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(progress.total, None);
+        assert_eq!(progress.bytes_per_second, 250);
+        assert_eq!(progress.eta_seconds, None);
+    }
+
+    #[test]
+    fn test_payload_shape() {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new("1.2.0", start);
+        let progress = tracker.record(10, None, start).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(progress).unwrap(),
+            serde_json::json!({
+                "version": "1.2.0",
+                "downloaded": 10,
+                "total": null,
+                "bytesPerSecond": 0,
+                "etaSeconds": null
+            })
+        );
+    }
+}
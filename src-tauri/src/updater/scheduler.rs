@@ -5,21 +5,23 @@
 use std::time::Duration;
 use tauri::Manager;
 
-use super::{check_for_update_silent, PendingUpdate};
+use super::{check_for_update_silent, emit_state, stage_update, PendingUpdate};
 
 /// Setup automatic update checking - runs periodically every 4 hours
-/// Updates are checked silently; dialog only shows when window gains focus
+/// Updates are checked and downloaded silently; the restart prompt only
+/// shows once the window has focus
 pub fn setup_auto_update_check(handle: &tauri::AppHandle) {
     let app = handle.clone();
 
     tauri::async_runtime::spawn(async move {
         loop {
-            // Check for updates silently
-            if let Some(version) = check_for_update_silent(&app).await {
-                // Store pending update version to show dialog on next focus
+            // Check for updates silently and download them in the background
+            if let Some(update) = check_for_update_silent(&app).await {
                 if let Some(state) = app.try_state::<PendingUpdate>() {
-                    state.set(version);
+                    state.set_available(&update.version);
                 }
+                emit_state(&app);
+                stage_update(&app, update).await;
             }
 
             // Wait 4 hours before next check
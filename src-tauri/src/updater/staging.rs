@@ -0,0 +1,176 @@
+//! Downloaded updates on disk
+//!
+//! A verified download is written to the `updates` folder in the app cache
+//! directory rather than kept in memory. It's installed from there when the
+//! user restarts or quits; if the app exits without installing it, the next
+//! launch finds the same release again and reuses the file instead of
+//! downloading it a second time.
+//!
+//! Each update is stored as `<version>.update` next to `<version>.sig`, the
+//! release signature it was downloaded for, so a file is only reused for the
+//! exact release it came from. `Update::install` doesn't check signatures and
+//! anything could have changed the file since, so it's verified again against
+//! the updater's public key every time it's read back.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use minisign_verify::{PublicKey, Signature};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Folder in the app cache directory holding downloaded updates
+pub const STAGING_DIR: &str = "updates";
+
+fn update_path(dir: &Path, version: &str) -> PathBuf {
+    dir.join(format!("{}.update", version))
+}
+
+fn signature_path(dir: &Path, version: &str) -> PathBuf {
+    dir.join(format!("{}.sig", version))
+}
+
+/// Write a verified update to `dir`, removing any other staged update
+///
+/// Returns the path of the written update.
+pub fn write(dir: &Path, version: &str, signature: &str, bytes: &[u8]) -> Result<PathBuf, String> {
+    // Removes this version's old signature too, so it can't vouch for a
+    // partly written update
+    clear(dir);
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let path = update_path(dir, version);
+    // Write the signature last, so a partly written update is never reused
+    write_atomically(&path, bytes)
+        .and_then(|()| write_atomically(&signature_path(dir, version), signature.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Write `contents` next to `path` and move it into place, so `path` is
+/// never left half written
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut partial = OsString::from(path.as_os_str());
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    fs::write(&partial, contents)?;
+    fs::rename(&partial, path)
+}
+
+/// The staged update for `version`, if it was downloaded for the release
+/// signed with `signature` and still matches it
+pub fn find(dir: &Path, version: &str, signature: &str, pubkey: &str) -> Option<PathBuf> {
+    let saved = fs::read_to_string(signature_path(dir, version)).ok()?;
+    if saved != signature {
+        return None;
+    }
+
+    let path = update_path(dir, version);
+    match read(&path, signature, pubkey) {
+        Ok(_) => Some(path),
+        Err(e) => {
+            eprintln!("⚠️ Not reusing downloaded update {}: {}", version, e);
+            None
+        }
+    }
+}
+
+/// Read a staged update, checking it against the release `signature`
+pub fn read(path: &Path, signature: &str, pubkey: &str) -> Result<Vec<u8>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    verify(&bytes, signature, pubkey)?;
+    Ok(bytes)
+}
+
+/// Check `bytes` against a release signature, with the signature and public
+/// key encoded as in the update manifest and `tauri.conf.json`
+fn verify(bytes: &[u8], signature: &str, pubkey: &str) -> Result<(), String> {
+    let decode = |encoded: &str| {
+        STANDARD
+            .decode(encoded)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+    };
+
+    let pubkey = decode(pubkey)
+        .and_then(|key| PublicKey::decode(&key).ok())
+        .ok_or_else(|| "Invalid updater public key".to_string())?;
+    let signature = decode(signature)
+        .and_then(|signature| Signature::decode(&signature).ok())
+        .ok_or_else(|| "Invalid update signature".to_string())?;
+    pubkey
+        .verify(bytes, &signature, true)
+        .map_err(|e| format!("Update signature doesn't match: {}", e))
+}
+
+/// Remove every staged update
+pub fn clear(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let _ = fs::remove_file(entry.path());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minisign key and its signature of `b"update"`, encoded like the
+    /// updater's `pubkey` and a manifest's `signature`
+    const PUBKEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IDA4MDcwNjA1MDQwMzAyMDEKUldRQkFnTUVCUVlIQ0FPaEI3L3p6aEMrSFhEZEdPZEx3SmxuNU5Zd202VU5YeDNjaG1RU1ZURzQK";
+    const SIGNATURE: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IHNpZ25hdHVyZSBmcm9tIHRhdXJpIHNlY3JldCBrZXkKUlVRQkFnTUVCUVlIQ01ZNUR0NHNuWmZXUlE4bVp5WW52UGgyVUpaT0dXdS9jWkhtWnc4UVZwSndtZUFKemt1MS9OUllKN2pBTXlncjB2NjZ2Uk5iYklld3BJZGN1Z2R1elFjPQp0cnVzdGVkIGNvbW1lbnQ6IHRpbWVzdGFtcDoxNzAwMDAwMDAwCWZpbGU6Y3VzaGlvbi5hcHAudGFyLmd6CjJiS0lBbWo1SisyQXM2b2NUNEY5MmQ0WUZ1WUVRc2tTSTc2cCt6VVhKQ1pDMmtGazFOV01pbVVYQWtKVFBYVElQQkR4djlUazhqbVRGT2hYYy9FTkFBPT0K";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("cushion-staging-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_staged_update_is_found_for_its_release() {
+        let dir = temp_dir("find");
+        let path = write(&dir, "1.2.0", SIGNATURE, b"update").unwrap();
+        assert_eq!(read(&path, SIGNATURE, PUBKEY).unwrap(), b"update");
+
+        assert_eq!(find(&dir, "1.2.0", SIGNATURE, PUBKEY), Some(path));
+        // A re-published release with the same version is downloaded again
+        assert_eq!(find(&dir, "1.2.0", "sig-b", PUBKEY), None);
+        assert_eq!(find(&dir, "1.3.0", SIGNATURE, PUBKEY), None);
+        // Only complete files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_changed_updates_are_not_reused() {
+        let dir = temp_dir("tampered");
+        let path = write(&dir, "1.2.0", SIGNATURE, b"update").unwrap();
+
+        for contents in [&b"updat"[..], b"malware"] {
+            fs::write(&path, contents).unwrap();
+            assert_eq!(find(&dir, "1.2.0", SIGNATURE, PUBKEY), None);
+            assert!(read(&path, SIGNATURE, PUBKEY).is_err());
+        }
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_staging_replaces_older_updates() {
+        let dir = temp_dir("replace");
+        write(&dir, "1.2.0", SIGNATURE, b"update").unwrap();
+        write(&dir, "1.3.0", "sig-b", b"new").unwrap();
+
+        assert_eq!(find(&dir, "1.2.0", SIGNATURE, PUBKEY), None);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        clear(&dir);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
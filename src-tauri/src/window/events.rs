@@ -13,11 +13,11 @@ use crate::updater;
 pub fn handle_window_event(window: &tauri::Window, event: &WindowEvent) {
     match event {
         WindowEvent::Focused(true) => {
-            // Check if there's a pending update to show when window gains focus
+            // Offer to restart into an update downloaded while unfocused
             if let Some(state) = window.app_handle().try_state::<updater::PendingUpdate>() {
-                if state.take().is_some() {
-                    println!("🔔 Showing pending update dialog on window focus");
-                    updater::show_update_dialog(window.app_handle(), false);
+                if let Some(version) = state.take_prompt() {
+                    println!("🔔 Offering restart to update on window focus");
+                    updater::prompt_restart(window.app_handle(), &version);
                 }
             }
         }
@@ -53,6 +53,18 @@ pub fn handle_run_event(app_handle: &tauri::AppHandle, event: RunEvent) {
                 api.prevent_exit();
             }
         }
+        RunEvent::Exit => {
+            // Apply an update downloaded in the background on quit; a
+            // failure is logged and the download is tried again next launch
+            let _ = updater::install_staged_update(app_handle);
+
+            // Show notifications still waiting in a batching window, and
+            // write history changes still waiting to be saved
+            if let Some(manager) = crate::notifications::NotificationManager::get() {
+                manager.flush_batches();
+                manager.flush_history();
+            }
+        }
         #[cfg(target_os = "macos")]
         RunEvent::Reopen { .. } => {
             println!("Reopen event received (dock icon clicked)");
@@ -6,6 +6,9 @@ mod events;
 
 use tauri::{WebviewUrl, WebviewWindowBuilder};
 
+#[cfg(target_os = "macos")]
+use crate::url_policy::UrlPolicy;
+
 #[cfg(target_os = "macos")]
 use tauri::TitleBarStyle;
 
@@ -30,16 +33,23 @@ pub fn create_window_builder<R: tauri::Runtime, M: tauri::Manager<R>>(app: &M) -
         win_builder = win_builder
             .title_bar_style(TitleBarStyle::Overlay)
             .traffic_light_position(tauri::LogicalPosition::new(15.0, 20.0))
-            .initialization_script(get_initialization_script());
+            .initialization_script(get_initialization_script(&app.state::<UrlPolicy>()));
     }
 
     win_builder
 }
 
 /// Get the JavaScript initialization script for the webview
+///
+/// Links to app hosts stay in the window; the host list comes from the URL
+/// policy so it matches what the Rust side trusts.
 #[cfg(target_os = "macos")]
-fn get_initialization_script() -> &'static str {
-    r#"
+fn get_initialization_script(policy: &UrlPolicy) -> String {
+    INITIALIZATION_SCRIPT.replace("__IS_APP_HOST__", &policy.app_host_matcher_js())
+}
+
+#[cfg(target_os = "macos")]
+const INITIALIZATION_SCRIPT: &str = r#"
         // Disable swipe navigation and overscroll bounce
         document.addEventListener('DOMContentLoaded', function() {
             document.body.style.overscrollBehaviorX = 'none';
@@ -105,6 +115,8 @@ fn get_initialization_script() -> &'static str {
         });
 
         // Handle link clicks
+        const isAppHost = __IS_APP_HOST__;
+
         document.addEventListener('click', async function(e) {
             let target = e.target;
             while (target && target.tagName !== 'A') {
@@ -113,9 +125,9 @@ fn get_initialization_script() -> &'static str {
 
             if (target && target.tagName === 'A' && target.href) {
                 const url = new URL(target.href);
+                const hasCredentials = url.username !== '' || url.password !== '';
                 const isInternal = url.origin === window.location.origin
-                    || url.hostname === 'cushion.so'
-                    || url.hostname.endsWith('.cushion.so')
+                    || (url.protocol === 'https:' && !hasCredentials && isAppHost(url.hostname))
                     || url.hostname === 'localhost';
 
                 if (isInternal) {
@@ -130,8 +142,7 @@ fn get_initialization_script() -> &'static str {
                 }
             }
         }, true);
-    "#
-}
+    "#;
 
 /// Recreate the main window (fallback for when window is unexpectedly destroyed)
 #[cfg(target_os = "macos")]